@@ -0,0 +1,52 @@
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use generatePDF::{generate_pdf_bytes, Generator, Options, PaddingStrategy};
+
+const SIZES: [usize; 3] = [10 << 10, 1 << 20, 16 << 20];
+
+fn generate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("generate_pdf_bytes");
+    group.sample_size(10);
+    for size in SIZES {
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            b.iter(|| generate_pdf_bytes(size, &Options::default()).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn generator(c: &mut Criterion) {
+    let mut group = c.benchmark_group("Generator::generate_into");
+    group.sample_size(10);
+    for size in SIZES {
+        group.throughput(Throughput::Bytes(size as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(size), &size, |b, &size| {
+            let mut generator = Generator::default();
+            let mut output = Vec::new();
+            b.iter(|| generator.generate_into(size, &mut output).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn padding_strategies(c: &mut Criterion) {
+    let mut group = c.benchmark_group("padding");
+    group.sample_size(10);
+    group.throughput(Throughput::Bytes(1 << 20));
+    for (name, padding) in [
+        ("text", PaddingStrategy::Text),
+        ("hex-text", PaddingStrategy::HexText),
+        ("raw-image", PaddingStrategy::RawImage),
+        ("jpeg-image", PaddingStrategy::JpegImage),
+        ("form-fields", PaddingStrategy::FormFields),
+        ("annotations", PaddingStrategy::Annotations),
+        ("calibration-page", PaddingStrategy::CalibrationPage),
+    ] {
+        let options = Options::default().padding(padding);
+        group.bench_function(name, |b| b.iter(|| generate_pdf_bytes(1 << 20, &options).unwrap()));
+    }
+    group.finish();
+}
+
+criterion_group!(benches, generate, generator, padding_strategies);
+criterion_main!(benches);
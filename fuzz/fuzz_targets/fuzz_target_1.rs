@@ -1,5 +1,7 @@
 #![no_main]
 
+use generatePDF::verify::{verify_pdf_bytes, SizeTarget};
+use generatePDF::{generate_pdf_bytes, Options};
 use libfuzzer_sys::fuzz_target;
 
 fuzz_target!(|byte_len: usize| {
@@ -11,6 +13,8 @@ fuzz_target!(|byte_len: usize| {
     let range = max_value - min_value;
     let byte_len: usize = min_value + (byte_len % range);
 
-    generatePDF::generate_pdf_with_size(byte_len).unwrap().save("fuzz.pdf").unwrap();
-    assert_eq!(std::fs::metadata("fuzz.pdf").unwrap().len() as usize, byte_len);
+    // the document never touches the disk, so parallel jobs do not share a file
+    let bytes = generate_pdf_bytes(byte_len, &Options::default()).unwrap();
+    let report = verify_pdf_bytes(&bytes, SizeTarget::Exact(byte_len));
+    assert!(report.is_valid(), "{report}");
 });
@@ -0,0 +1,36 @@
+/* C interface of generatePDF, built with `cargo rustc --lib --release --features ffi --crate-type cdylib`. */
+
+#ifndef GENERATEPDF_H
+#define GENERATEPDF_H
+
+#include <stddef.h>
+#include <stdint.h>
+
+#ifdef __cplusplus
+extern "C" {
+#endif
+
+#define GENERATEPDF_OK 0
+/* The requested size is smaller than the smallest document which can be generated. */
+#define GENERATEPDF_ERROR_FILE_TOO_SMALL 1
+/* A pointer is null or the path is not valid UTF-8. */
+#define GENERATEPDF_ERROR_INVALID_ARGUMENT 2
+/* Writing the file failed. */
+#define GENERATEPDF_ERROR_IO 3
+/* Building or serializing the document failed. */
+#define GENERATEPDF_ERROR_PDF 4
+
+/* Fills the buffer_len bytes at buffer with a document of exactly that size. */
+int generatepdf_fill_buffer(uint8_t *buffer, size_t buffer_len);
+
+/* Saves a document of exactly file_size_bytes bytes to the file at path, replacing it if it exists. */
+int generatepdf_save(const char *path, size_t file_size_bytes);
+
+/* Stores the smallest size a document can be generated with at minimum. */
+int generatepdf_minimum_size(size_t *minimum);
+
+#ifdef __cplusplus
+}
+#endif
+
+#endif
@@ -0,0 +1,58 @@
+//! Writing generated documents to async writers, available with the `tokio` feature.
+
+use tokio::io::{AsyncWrite, AsyncWriteExt};
+
+use crate::channel::write_in_background;
+use crate::{generate_pdf_with_options, Error, Options};
+
+/// Generates a document which is exactly `file_size_bytes` bytes large and writes it to `writer`.
+pub async fn generate_pdf_to_async_writer(file_size_bytes: usize, writer: impl AsyncWrite + Unpin) -> Result<(), Error> {
+    generate_pdf_to_async_writer_with_options(file_size_bytes, &Options::default(), writer).await
+}
+
+/// Generates a document like [`generate_pdf_with_options`] and writes it to `writer`. Generating and
+/// serializing the document happens on a separate thread, so the executor is not blocked.
+pub async fn generate_pdf_to_async_writer_with_options(
+    file_size_bytes: usize,
+    options: &Options,
+    mut writer: impl AsyncWrite + Unpin,
+) -> Result<(), Error> {
+    let options = options.clone();
+    let mut chunks = write_in_background(move |mut writer| {
+        Ok(generate_pdf_with_options(file_size_bytes, &options)?.save_to(&mut writer)?)
+    });
+    while let Some(chunk) = chunks.recv().await {
+        writer.write_all(&chunk?).await?;
+    }
+    Ok(writer.flush().await?)
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::Document;
+
+    use super::generate_pdf_to_async_writer_with_options;
+    use crate::verify::{verify_pdf_bytes, SizeTarget};
+    use crate::{Error, Options, PaddingStrategy};
+
+    #[tokio::test]
+    async fn writes_documents() {
+        // larger than the chunks sent through the channel
+        for (size, padding) in [(2_000, PaddingStrategy::Text), (1_000_000, PaddingStrategy::RawImage)] {
+            let mut bytes = Vec::new();
+            let options = Options::default().padding(padding).page_count(2);
+            generate_pdf_to_async_writer_with_options(size, &options, &mut bytes).await.unwrap();
+            assert_eq!(bytes.len(), size);
+            assert!(verify_pdf_bytes(&bytes, SizeTarget::Exact(size)).is_valid());
+            assert_eq!(Document::load_mem(&bytes).unwrap().get_pages().len(), 2);
+        }
+    }
+
+    #[tokio::test]
+    async fn fails_without_writing() {
+        let mut bytes = Vec::new();
+        let result = generate_pdf_to_async_writer_with_options(100, &Options::default(), &mut bytes).await;
+        assert!(matches!(result, Err(Error::FileTooSmall { requested: 100, .. })), "{result:?}");
+        assert!(bytes.is_empty());
+    }
+}
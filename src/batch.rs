@@ -0,0 +1,222 @@
+//! Generating a whole corpus of documents declared in a manifest, available with the `batch`
+//! feature.
+//!
+//! A manifest lists the documents as `output` entries. In TOML:
+//!
+//! ```toml
+//! [[output]]
+//! path = "upload-limit.pdf"
+//! size = 10485760
+//! pages = 3
+//! padding = "jpeg-image"
+//! title = "Upload limit"
+//! ```
+//!
+//! JSON manifests, recognized by the `.json` extension, have the same structure. Only `path` and
+//! `size` are required, the other keys correspond to the methods of [`Options`]. Paths are relative
+//! to the manifest, missing directories are created. All documents are generated
+//! [deterministically](Options::deterministic), so generating a manifest again reproduces the same
+//! files.
+
+use std::path::{Path, PathBuf};
+
+use serde::Deserialize;
+
+use crate::{generate_pdf_with_options, Error, Options};
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Manifest {
+    pub output: Vec<Entry>,
+}
+
+/// A document to generate.
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields, rename_all = "kebab-case")]
+pub struct Entry {
+    pub path: PathBuf,
+    pub size: usize,
+    pub pages: Option<usize>,
+    /// The exact size of the content stream of every page, see
+    /// [`Options::page_content_sizes`](crate::Options::page_content_sizes).
+    #[serde(default)]
+    pub page_content_sizes: Vec<usize>,
+    /// The name of a [`PaddingStrategy`](crate::PaddingStrategy), e.g. `jpeg-image`.
+    pub padding: Option<String>,
+    /// The name of a [`PageSize`](crate::PageSize) or `<width>x<height>`.
+    pub page_size: Option<String>,
+    pub pdf_version: Option<String>,
+    /// A [`TrailerId`](crate::TrailerId): `auto`, `none`, `seed:<number>` or hexadecimal digits.
+    pub trailer_id: Option<String>,
+    pub form_fields: Option<usize>,
+    pub annotations_per_page: Option<usize>,
+    pub bookmarks: Option<usize>,
+    pub bookmark_depth: Option<usize>,
+    pub signature_placeholder: Option<usize>,
+    pub block_size: Option<usize>,
+    pub decompressed_size: Option<usize>,
+    /// The names of [`StreamFilter`](crate::StreamFilter)s in decoding order, e.g. `ascii85`.
+    #[serde(default)]
+    pub stream_filters: Vec<String>,
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub producer: Option<String>,
+    pub creation_date: Option<String>,
+    pub caption: Option<String>,
+    /// The name of the [`CjkFont`](crate::CjkFont) to print the caption in, e.g. `japanese`.
+    pub unicode_caption: Option<String>,
+    #[serde(default)]
+    pub xmp_metadata: bool,
+    #[serde(default)]
+    pub padding_checksum: bool,
+    #[serde(default)]
+    pub benign_javascript: bool,
+}
+
+impl Manifest {
+    /// Reads a TOML or JSON manifest, depending on the extension of `path`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, Error> {
+        let path = path.as_ref();
+        let manifest = std::fs::read_to_string(path)?;
+        let manifest = if path.extension().is_some_and(|extension| extension == "json") {
+            serde_json::from_str(&manifest).map_err(|error| error.to_string())
+        } else {
+            toml::from_str(&manifest).map_err(|error| error.to_string())
+        };
+        manifest.map_err(|error| Error::InvalidOption(format!("Invalid manifest {}: {error}", path.display())))
+    }
+
+    /// Generates every entry, resolving relative paths against `base_dir` and creating the
+    /// directories they lead through.
+    pub fn generate(&self, base_dir: impl AsRef<Path>) -> Result<(), Error> {
+        for entry in &self.output {
+            let mut doc = generate_pdf_with_options(entry.size, &entry.options()?)?;
+            let path = base_dir.as_ref().join(&entry.path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            doc.save(path)?;
+        }
+        Ok(())
+    }
+}
+
+impl Entry {
+    pub fn options(&self) -> Result<Options, Error> {
+        let mut options = Options::default()
+            .deterministic(true)
+            .xmp_metadata(self.xmp_metadata)
+            .padding_checksum(self.padding_checksum)
+            .benign_javascript(self.benign_javascript);
+        if let Some(pages) = self.pages {
+            options = options.page_count(pages);
+        }
+        if !self.page_content_sizes.is_empty() {
+            options = options.page_content_sizes(self.page_content_sizes.iter().copied());
+        }
+        if let Some(font) = &self.unicode_caption {
+            options = options.unicode_caption(font.parse()?);
+        }
+        if let Some(padding) = &self.padding {
+            options = options.padding(padding.parse()?);
+        }
+        if let Some(page_size) = &self.page_size {
+            options = options.page_size(page_size.parse()?);
+        }
+        if let Some(pdf_version) = &self.pdf_version {
+            options = options.pdf_version(pdf_version.parse()?);
+        }
+        if let Some(trailer_id) = &self.trailer_id {
+            options = options.trailer_id(trailer_id.parse()?);
+        }
+        if let Some(form_fields) = self.form_fields {
+            options = options.form_fields(form_fields);
+        }
+        if let Some(annotations_per_page) = self.annotations_per_page {
+            options = options.annotations_per_page(annotations_per_page);
+        }
+        if let Some(bookmarks) = self.bookmarks {
+            options = options.bookmarks(bookmarks);
+        }
+        if let Some(bookmark_depth) = self.bookmark_depth {
+            options = options.bookmark_depth(bookmark_depth);
+        }
+        if let Some(title) = &self.title {
+            options = options.title(title);
+        }
+        if let Some(author) = &self.author {
+            options = options.author(author);
+        }
+        if let Some(subject) = &self.subject {
+            options = options.subject(subject);
+        }
+        if let Some(producer) = &self.producer {
+            options = options.producer(producer);
+        }
+        if let Some(creation_date) = &self.creation_date {
+            options = options.creation_date(creation_date);
+        }
+        if let Some(len) = self.signature_placeholder {
+            options = options.signature_placeholder(len);
+        }
+        if let Some(block_size) = self.block_size {
+            options = options.block_size(block_size);
+        }
+        if let Some(len) = self.decompressed_size {
+            options = options.decompressed_size(len);
+        }
+        if !self.stream_filters.is_empty() {
+            let filters = self.stream_filters.iter().map(|filter| filter.parse()).collect::<Result<Vec<_>, _>>()?;
+            options = options.stream_filters(filters);
+        }
+        if let Some(caption) = &self.caption {
+            options = options.caption(caption);
+        }
+        Ok(options)
+    }
+}
+
+/// Generates all documents of the manifest at `path`.
+pub fn run(path: impl AsRef<Path>) -> Result<(), Error> {
+    let path = path.as_ref();
+    Manifest::load(path)?.generate(path.parent().unwrap_or(Path::new("")))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::run;
+    use crate::verify::{verify_pdf, SizeTarget};
+
+    #[test]
+    fn runs_a_manifest_into_nested_directories() {
+        let dir = std::env::temp_dir().join(format!("generatePDF-batch-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let manifest = dir.join("manifest.toml");
+        std::fs::write(
+            &manifest,
+            r#"
+[[output]]
+path = "small.pdf"
+size = 2000
+
+[[output]]
+path = "limits/upload/large.pdf"
+size = 100000
+padding = "jpeg-image"
+pages = 2
+title = "Upload limit"
+"#,
+        )
+        .unwrap();
+
+        let result = run(&manifest);
+        let reports = [("small.pdf", 2000), ("limits/upload/large.pdf", 100_000)]
+            .map(|(path, size)| verify_pdf(dir.join(path), SizeTarget::Exact(size)).map(|report| report.is_valid()));
+        std::fs::remove_dir_all(&dir).unwrap();
+        result.unwrap();
+        for report in reports {
+            assert!(report.unwrap());
+        }
+    }
+}
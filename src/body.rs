@@ -0,0 +1,84 @@
+//! Serving generated documents over HTTP without serializing them into memory first, available with
+//! the `http-body` feature.
+//!
+//! [`PdfBody`] implements [`http_body::Body`], so it can be returned from hyper services and, by
+//! way of `axum::body::Body::new`, from axum handlers. The document is generated on a separate
+//! thread as the client reads it. Documents the [native writer](crate::native) supports are
+//! streamed in constant memory. Any other document is held in memory as a whole while it is
+//! serialized, so every such body being sent takes about as much memory as its document is large.
+
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+use bytes::Bytes;
+use http::header::{CONTENT_LENGTH, CONTENT_TYPE};
+use http_body::{Body, Frame, SizeHint};
+use tokio::sync::mpsc;
+
+use crate::channel::write_in_background;
+use crate::generator::Scratch;
+use crate::{build_solved, solve_layout, Error, Options};
+
+/// The body of a response carrying a document of an exact size.
+pub struct PdfBody {
+    chunks: mpsc::Receiver<Result<Vec<u8>, Error>>,
+    remaining: u64,
+}
+
+impl PdfBody {
+    /// Starts generating a document which is exactly `file_size_bytes` bytes large on a separate
+    /// thread, so this returns without blocking the executor. Options which contradict each other
+    /// fail here, a size which cannot be generated fails the body when it is sent.
+    pub fn new(file_size_bytes: usize, options: &Options) -> Result<Self, Error> {
+        options.validate()?;
+        options.validate_size(file_size_bytes)?;
+        let options = options.clone();
+        let chunks = write_in_background(move |mut writer| {
+            #[cfg(feature = "native-writer")]
+            if crate::native::supports(&options) {
+                return crate::native::write_pdf(file_size_bytes, &options, writer);
+            }
+            let mut scratch = Scratch::default();
+            let (id, layout) = solve_layout(file_size_bytes, &options, &mut scratch)?;
+            let mut doc = build_solved(file_size_bytes, &options, id.as_deref(), layout, &mut scratch)?;
+            Ok(doc.save_to(&mut writer)?)
+        });
+        Ok(PdfBody { chunks, remaining: file_size_bytes as u64 })
+    }
+
+    /// Wraps the body into a response with the `Content-Type` and `Content-Length` of the document.
+    pub fn into_response(self) -> http::Response<Self> {
+        let content_length = self.remaining;
+        let mut response = http::Response::new(self);
+        let headers = response.headers_mut();
+        headers.insert(CONTENT_TYPE, http::HeaderValue::from_static("application/pdf"));
+        headers.insert(CONTENT_LENGTH, content_length.into());
+        response
+    }
+}
+
+impl Body for PdfBody {
+    type Data = Bytes;
+    type Error = std::io::Error;
+
+    fn poll_frame(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Result<Frame<Bytes>, Self::Error>>> {
+        self.chunks.poll_recv(cx).map(|chunk| {
+            chunk.map(|chunk| match chunk {
+                Ok(chunk) => {
+                    self.remaining -= chunk.len() as u64;
+                    Ok(Frame::data(chunk.into()))
+                }
+                Err(Error::IOError(error)) => Err(error),
+                Err(error) => Err(std::io::Error::other(error.to_string())),
+            })
+        })
+    }
+
+    fn is_end_stream(&self) -> bool {
+        self.remaining == 0
+    }
+
+    fn size_hint(&self) -> SizeHint {
+        SizeHint::with_exact(self.remaining)
+    }
+}
@@ -0,0 +1,99 @@
+//! Writing a document on a separate thread, handing the bytes to async code in chunks.
+
+use std::io::Write;
+
+use tokio::sync::mpsc;
+
+use crate::Error;
+
+/// Size of the chunks sent through the channel.
+const CHUNK_SIZE: usize = 64 * 1024;
+/// Amount of chunks serialized ahead of the receiver.
+const CHUNKS_AHEAD: usize = 16;
+
+/// Runs `write` on a new thread and sends what it writes in chunks. An error ends the channel, as
+/// does dropping the receiver.
+pub(crate) fn write_in_background<W>(write: W) -> mpsc::Receiver<Result<Vec<u8>, Error>>
+where
+    W: FnOnce(&mut dyn Write) -> Result<(), Error> + Send + 'static,
+{
+    let (sender, chunks) = mpsc::channel(CHUNKS_AHEAD);
+    std::thread::spawn(move || {
+        let mut writer = ChannelWriter { sender, chunk: Vec::with_capacity(CHUNK_SIZE) };
+        let result = write(&mut writer).and_then(|()| Ok(writer.flush()?));
+        if let Err(error) = result {
+            // fails as well if the receiver has been dropped, in which case nobody is listening
+            let _ = writer.sender.blocking_send(Err(error));
+        }
+    });
+    chunks
+}
+
+/// Sends everything written to it in chunks of [`CHUNK_SIZE`] bytes, waiting while the channel is
+/// full.
+struct ChannelWriter {
+    sender: mpsc::Sender<Result<Vec<u8>, Error>>,
+    chunk: Vec<u8>,
+}
+
+impl Write for ChannelWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let len = buf.len().min(CHUNK_SIZE - self.chunk.len());
+        self.chunk.extend_from_slice(&buf[..len]);
+        if self.chunk.len() == CHUNK_SIZE {
+            self.flush()?;
+        }
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        if self.chunk.is_empty() {
+            return Ok(());
+        }
+        let chunk = std::mem::replace(&mut self.chunk, Vec::with_capacity(CHUNK_SIZE));
+        self.sender
+            .blocking_send(Ok(chunk))
+            .map_err(|_| std::io::Error::new(std::io::ErrorKind::BrokenPipe, "the receiver has been dropped"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::ErrorKind;
+    use std::time::Duration;
+
+    use super::{write_in_background, CHUNK_SIZE};
+    use crate::Error;
+
+    #[test]
+    fn dropping_the_receiver_ends_the_thread() {
+        let (finished, outcome) = std::sync::mpsc::channel();
+        let mut chunks = write_in_background(move |writer| {
+            // writes until the receiver is gone
+            let result = std::iter::repeat(()).try_for_each(|()| writer.write_all(&[b'4'; 1000]));
+            finished.send(result.unwrap_err().kind()).unwrap();
+            Ok(())
+        });
+        assert_eq!(chunks.blocking_recv().unwrap().unwrap().len(), CHUNK_SIZE);
+        drop(chunks);
+        assert_eq!(outcome.recv_timeout(Duration::from_secs(10)), Ok(ErrorKind::BrokenPipe));
+    }
+
+    #[test]
+    fn sends_everything_written_and_then_the_error() {
+        let mut chunks = write_in_background(|writer| {
+            writer.write_all(&vec![b'4'; 2 * CHUNK_SIZE + 1])?;
+            Err(Error::InvalidOption("failed after writing".into()))
+        });
+        let mut written = 0;
+        let error = loop {
+            match chunks.blocking_recv().unwrap() {
+                Ok(chunk) => written += chunk.len(),
+                Err(error) => break error,
+            }
+        };
+        assert_eq!(written, 2 * CHUNK_SIZE);
+        assert!(matches!(error, Error::InvalidOption(_)), "{error}");
+        assert!(chunks.blocking_recv().is_none());
+    }
+}
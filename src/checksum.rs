@@ -0,0 +1,170 @@
+//! A SHA-256 checksum of the padding, stored in the document information dictionary so the file can
+//! be checked for truncation or modification without a sidecar file.
+//!
+//! The checksum covers the stream data of the content streams page by page in the order of
+//! `/Contents`, followed by the data of the images drawn by them in the order of their names, the
+//! values of the form fields in the order of the `/Fields` of the interactive form and the contents
+//! of the other annotations page by page in the order of `/Annots`, exactly as stored in the file.
+//! The content streams include those of every page sized by
+//! [`Options::page_content_sizes`](crate::Options::page_content_sizes).
+
+use lopdf::{Document, IncrementalDocument, Object, ObjectId, StringFormat};
+use sha2::{Digest, Sha256};
+
+use crate::Error;
+
+/// Key of the checksum in the document information dictionary.
+pub const CHECKSUM_KEY: &str = "PaddingSHA256";
+
+/// A string as long as the lowercase hex encoded checksum, so the size of the document does not
+/// change once the actual checksum is filled in.
+pub(crate) fn placeholder() -> Object {
+    Object::String(vec![b'0'; 2 * Sha256::output_size()], StringFormat::Literal)
+}
+
+/// The objects of a document which hold padding, each in the order the checksum covers them.
+pub(crate) struct PaddingObjects {
+    pub content_ids: Vec<ObjectId>,
+    pub xobject_ids: Vec<ObjectId>,
+    pub field_ids: Vec<ObjectId>,
+    pub annotation_ids: Vec<ObjectId>,
+}
+
+/// Replaces the checksum in the information dictionary `info_id` of `doc` with the checksum of
+/// the given padding objects.
+pub(crate) fn embed(doc: &mut Document, info_id: ObjectId, padding: &PaddingObjects) -> Result<(), Error> {
+    let checksum = checksum(&[doc], padding)?;
+    set(doc, info_id, checksum)
+}
+
+/// Like [`embed`] for the update of `doc`, whose padding objects may be defined in earlier
+/// revisions as well.
+pub(crate) fn embed_in_update(
+    doc: &mut IncrementalDocument,
+    info_id: ObjectId,
+    padding: &PaddingObjects,
+) -> Result<(), Error> {
+    let checksum = checksum(&[&doc.new_document, doc.get_prev_documents()], padding)?;
+    set(&mut doc.new_document, info_id, checksum)
+}
+
+/// Hashes the padding objects, taking each from the first of `revisions` defining it.
+fn checksum(revisions: &[&Document], padding: &PaddingObjects) -> Result<String, Error> {
+    let object = |id: ObjectId| {
+        let revision = revisions.iter().find(|doc| doc.objects.contains_key(&id)).unwrap_or(&revisions[0]);
+        revision.get_object(id)
+    };
+    let mut hasher = Sha256::new();
+    for id in padding.content_ids.iter().chain(&padding.xobject_ids) {
+        hasher.update(&object(*id)?.as_stream()?.content);
+    }
+    for id in &padding.field_ids {
+        hasher.update(object(*id)?.as_dict()?.get(b"V")?.as_str()?);
+    }
+    for id in &padding.annotation_ids {
+        hasher.update(object(*id)?.as_dict()?.get(b"Contents")?.as_str()?);
+    }
+    Ok(hasher.finalize().iter().map(|byte| format!("{byte:02x}")).collect())
+}
+
+fn set(doc: &mut Document, info_id: ObjectId, checksum: String) -> Result<(), Error> {
+    doc.get_dictionary_mut(info_id)?
+        .set(CHECKSUM_KEY, Object::String(checksum.into_bytes(), StringFormat::Literal));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::{Document, Object};
+    use sha2::{Digest, Sha256};
+
+    use super::CHECKSUM_KEY;
+    use crate::incremental::generate_incremental_pdf_with_options;
+    use crate::{generate_pdf_bytes, Options, PaddingStrategy};
+
+    /// Recomputes the checksum from the objects of a saved document in the order of the module
+    /// documentation.
+    fn recompute(doc: &Document) -> String {
+        let mut hasher = Sha256::new();
+        let pages = doc.get_pages();
+        for &page_id in pages.values() {
+            let contents = match doc.get_dictionary(page_id).unwrap().get(b"Contents") {
+                Ok(Object::Array(contents)) => contents.clone(),
+                Ok(content) => vec![content.clone()],
+                Err(_) => Vec::new(),
+            };
+            for content in contents {
+                hasher.update(&doc.get_object(content.as_reference().unwrap()).unwrap().as_stream().unwrap().content);
+            }
+        }
+        let root = doc.catalog().unwrap().get(b"Pages").unwrap().as_reference().unwrap();
+        let resources = doc.get_dictionary(root).unwrap().get(b"Resources").unwrap().as_reference().unwrap();
+        if let Ok(xobjects) = doc.get_dictionary(resources).unwrap().get(b"XObject") {
+            let mut xobjects: Vec<_> = xobjects.as_dict().unwrap().iter().collect();
+            xobjects.sort_by_key(|(name, _)| name.to_vec());
+            for (_, id) in xobjects {
+                hasher.update(&doc.get_object(id.as_reference().unwrap()).unwrap().as_stream().unwrap().content);
+            }
+        }
+        let dictionary = |object: &Object| doc.get_dictionary(object.as_reference().unwrap()).unwrap();
+        if let Ok(form) = doc.catalog().unwrap().get(b"AcroForm") {
+            for field in form.as_dict().unwrap().get(b"Fields").unwrap().as_array().unwrap() {
+                let field = dictionary(field);
+                if field.get(b"FT").unwrap().as_name().unwrap() == b"Tx" {
+                    hasher.update(field.get(b"V").unwrap().as_str().unwrap());
+                }
+            }
+        }
+        for &page_id in pages.values() {
+            let Ok(annotations) = doc.get_dictionary(page_id).unwrap().get(b"Annots") else {
+                continue;
+            };
+            for annotation in annotations.as_array().unwrap().iter().map(dictionary) {
+                if annotation.get(b"Subtype").unwrap().as_name().unwrap() != b"Widget" {
+                    hasher.update(annotation.get(b"Contents").unwrap().as_str().unwrap());
+                }
+            }
+        }
+        hasher.finalize().iter().map(|byte| format!("{byte:02x}")).collect()
+    }
+
+    fn stored(doc: &Document) -> String {
+        let info = doc.trailer.get(b"Info").unwrap().as_reference().unwrap();
+        let checksum = doc.get_dictionary(info).unwrap().get(CHECKSUM_KEY.as_bytes()).unwrap();
+        String::from_utf8(checksum.as_str().unwrap().to_vec()).unwrap()
+    }
+
+    #[test]
+    fn checksum_matches_the_saved_padding() {
+        let option_sets = PaddingStrategy::ALL
+            .map(|padding| Options::default().padding(padding).form_fields(2).annotations_per_page(2).page_count(2))
+            .into_iter()
+            .chain([
+                Options::default()
+                    .padding(PaddingStrategy::FormFields)
+                    .page_count(3)
+                    .page_content_sizes([2_000, 300, 40]),
+                Options::default().padding(PaddingStrategy::Annotations).page_count(2).page_content_sizes([500, 6_000]),
+                Options::default().content_stream_limit(10_000),
+            ]);
+        for options in option_sets {
+            let options = options.padding_checksum(true);
+            let doc = Document::load_mem(&generate_pdf_bytes(50_000, &options).unwrap()).unwrap();
+            assert_eq!(stored(&doc), recompute(&doc), "{options:?}");
+        }
+    }
+
+    #[test]
+    fn checksum_matches_the_padding_of_the_last_update() {
+        for options in [
+            Options::default().padding(PaddingStrategy::FormFields).page_count(2).page_content_sizes([2_000, 300]),
+            Options::default().padding(PaddingStrategy::RawImage).annotations_per_page(1),
+        ] {
+            let options = options.padding_checksum(true);
+            let mut bytes = Vec::new();
+            generate_incremental_pdf_with_options(50_000, 2, &options).unwrap().save_to(&mut bytes).unwrap();
+            let doc = Document::load_mem(&bytes).unwrap();
+            assert_eq!(stored(&doc), recompute(&doc), "{options:?}");
+        }
+    }
+}
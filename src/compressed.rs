@@ -0,0 +1,40 @@
+//! The content stream of [`Options::decompressed_size`]: hidden text compressed with `FlateDecode`,
+//! which takes up little of the file but expands to the requested amount of data once decoded.
+//!
+//! A run of the same byte compresses about a thousandfold, so the filler is compressed in chunks
+//! and never held in memory as a whole. The compressed data only depends on the size and the filler
+//! byte, so it is computed once and kept in the [`Scratch`] for all candidates of the padding.
+
+use std::io::Write;
+
+use flate2::write::ZlibEncoder;
+use flate2::Compression;
+use lopdf::{dictionary, Stream};
+
+use crate::generator::Scratch;
+use crate::{fill, filler_byte, Error, Options, HIDDEN_TEXT_END, HIDDEN_TEXT_START};
+
+/// Amount of filler bytes compressed at once.
+const CHUNK_SIZE: usize = 1 << 16;
+
+/// The smallest decompressed size, the operators around an empty string.
+pub(crate) const MIN_DECOMPRESSED_SIZE: usize = HIDDEN_TEXT_START.len() + HIDDEN_TEXT_END.len();
+
+/// Creates a content stream which decodes to exactly `size` bytes.
+pub(crate) fn content_stream(size: usize, options: &Options, scratch: &mut Scratch) -> Result<Stream, Error> {
+    let content = scratch.compressed(size, filler_byte(options), || compress(size, options))?;
+    Ok(Stream::new(dictionary! { "Filter" => "FlateDecode" }, content))
+}
+
+fn compress(size: usize, options: &Options) -> Result<Vec<u8>, Error> {
+    let filler = size - MIN_DECOMPRESSED_SIZE;
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::best());
+    encoder.write_all(HIDDEN_TEXT_START)?;
+    let mut chunk = vec![0; CHUNK_SIZE.min(filler)];
+    fill(&mut chunk, options);
+    for start in (0..filler).step_by(CHUNK_SIZE) {
+        encoder.write_all(&chunk[..CHUNK_SIZE.min(filler - start)])?;
+    }
+    encoder.write_all(HIDDEN_TEXT_END)?;
+    Ok(encoder.finish()?)
+}
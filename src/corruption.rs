@@ -0,0 +1,218 @@
+//! Intentionally malformed PDF files of an exact size, used to exercise the recovery paths of PDF
+//! parsers.
+//!
+//! Every corruption starts from a regular document produced by [`generate_pdf_with_size`] and only
+//! ever replaces bytes with the same amount of other bytes, so the size guarantee still holds.
+//! Corruptions which concern the object graph are applied to the [`Document`] before it is saved,
+//! the remaining ones patch the serialized bytes.
+
+use lopdf::{Document, Object};
+
+use crate::{first_page, first_page_contents, generate_pdf_with_size, save_to_vec, Error};
+
+/// The kind of damage done to the generated document.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CorruptionKind {
+    /// The `/Length` of the page content stream does not match the actual length of the stream.
+    WrongStreamLength,
+    /// The cross reference stream is cut off halfway and the remainder is blanked with spaces.
+    TruncatedXref,
+    /// The page refers to a content stream object which does not exist.
+    DanglingReference,
+    /// The `startxref` offset does not point to the cross reference stream.
+    MismatchedStartxref,
+    /// Two indirect objects are defined with the same object ID.
+    DuplicateObjectId,
+}
+
+impl CorruptionKind {
+    pub const ALL: [CorruptionKind; 5] = [
+        CorruptionKind::WrongStreamLength,
+        CorruptionKind::TruncatedXref,
+        CorruptionKind::DanglingReference,
+        CorruptionKind::MismatchedStartxref,
+        CorruptionKind::DuplicateObjectId,
+    ];
+}
+
+/// Generates a PDF file of exactly `file_size_bytes` bytes which is broken in the way described by
+/// `kind`.
+pub fn generate_corrupted_pdf_with_size(file_size_bytes: usize, kind: CorruptionKind) -> Result<Vec<u8>, Error> {
+    let mut doc = generate_pdf_with_size(file_size_bytes)?;
+
+    match kind {
+        CorruptionKind::WrongStreamLength => {
+            let content_id = first_page_contents(&doc)?[0];
+            let dict = &mut doc.get_object_mut(content_id)?.as_stream_mut()?.dict;
+            let length = dict.get(b"Length")?.as_i64()?;
+            dict.set("Length", same_width_variant(length));
+        }
+        CorruptionKind::DanglingReference => {
+            let content_id = first_page_contents(&doc)?[0];
+            let dangling_id = unused_id_with_width_of(&doc, content_id.0);
+            let page_id = first_page(&doc)?;
+            doc.get_dictionary_mut(page_id)?.set("Contents", Object::Reference((dangling_id, 0)));
+        }
+        _ => {}
+    }
+
+    // lopdf assigns the next free ID to the cross reference stream on save
+    let ids: Vec<u32> = doc.objects.keys().map(|&(id, _)| id).collect();
+    let mut bytes = save_to_vec(&mut doc)?;
+
+    match kind {
+        CorruptionKind::TruncatedXref => {
+            let startxref = find_startxref(&bytes);
+            let xref_start = parse_decimal(&bytes[startxref]);
+            let xref_end = find_last(&bytes, b"\nstartxref\n");
+            let cut = xref_start + (xref_end - xref_start) / 2;
+            bytes[cut..xref_end].fill(b' ');
+        }
+        CorruptionKind::MismatchedStartxref => {
+            let startxref = find_startxref(&bytes);
+            let offset = parse_decimal(&bytes[startxref.clone()]) as i64;
+            bytes[startxref].copy_from_slice(same_width_variant(offset).to_string().as_bytes());
+        }
+        CorruptionKind::DuplicateObjectId => {
+            // objects are written in ascending order, so the first two objects with equally wide
+            // IDs can simply swap their headers
+            let (original, duplicate) = ids
+                .windows(2)
+                .map(|pair| (pair[0], pair[1]))
+                .find(|(a, b)| digits(*a as usize) == digits(*b as usize))
+                .expect("the document skeleton always contains objects 1 and 2");
+            let header = format!("\n{duplicate} 0 obj\n");
+            let position = find_first(&bytes, header.as_bytes()) + 1;
+            let replacement = original.to_string();
+            bytes[position..position + replacement.len()].copy_from_slice(replacement.as_bytes());
+        }
+        _ => {}
+    }
+
+    Ok(bytes)
+}
+
+/// Finds an object ID printed with as many digits as `id` which is neither used by the document nor
+/// reserved for the cross reference stream.
+fn unused_id_with_width_of(doc: &Document, id: u32) -> u32 {
+    let width = digits(id as usize) as u32;
+    let reserved = doc.max_id + 1;
+    (10u32.pow(width - 1)..10u32.pow(width))
+        .rev()
+        .find(|candidate| *candidate != reserved && !doc.objects.contains_key(&(*candidate, 0)))
+        .expect("the document skeleton leaves unused object IDs")
+}
+
+/// Produces a different non-negative number with the same amount of decimal digits by bumping the
+/// most significant digit.
+fn same_width_variant(value: i64) -> i64 {
+    let magnitude = 10i64.pow(digits(value as usize) as u32 - 1);
+    let lead = value / magnitude;
+    let new_lead = if lead == 9 { 1 } else { lead + 1 };
+    value + (new_lead - lead) * magnitude
+}
+
+/// Returns the range of the offset following the final `startxref` keyword.
+fn find_startxref(bytes: &[u8]) -> std::ops::Range<usize> {
+    let start = find_last(bytes, b"startxref\n") + b"startxref\n".len();
+    let end = start + bytes[start..].iter().take_while(|b| b.is_ascii_digit()).count();
+    start..end
+}
+
+fn parse_decimal(digits: &[u8]) -> usize {
+    digits.iter().fold(0, |acc, digit| acc * 10 + (digit - b'0') as usize)
+}
+
+fn find_first(haystack: &[u8], needle: &[u8]) -> usize {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+        .expect("lopdf always writes this structure")
+}
+
+fn find_last(haystack: &[u8], needle: &[u8]) -> usize {
+    haystack
+        .windows(needle.len())
+        .rposition(|window| window == needle)
+        .expect("lopdf always writes this structure")
+}
+
+fn digits(value: usize) -> usize {
+    value.checked_ilog10().unwrap_or(0) as usize + 1
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::Document;
+
+    use super::{find_startxref, generate_corrupted_pdf_with_size, parse_decimal, CorruptionKind};
+    use crate::verify::{verify_pdf_bytes, Problem, SizeTarget};
+    use crate::{first_page, minimum_size, Options};
+
+    /// Generates documents with the corruption `kind` of sizes from the minimum and around the
+    /// powers of ten, where the numbers the corruptions replace gain digits, and checks them.
+    fn assert_every_size(kind: CorruptionKind, check: impl Fn(&[u8], &[Problem])) {
+        let minimum = minimum_size(&Options::default()).unwrap();
+        for size in (minimum..minimum + 16).chain(10_000 - 16..10_000 + 16).chain(100_000 - 16..100_000 + 16) {
+            let bytes = generate_corrupted_pdf_with_size(size, kind).unwrap();
+            assert_eq!(bytes.len(), size, "{kind:?}");
+            let report = verify_pdf_bytes(&bytes, SizeTarget::Exact(size));
+            check(&bytes, &report.problems);
+        }
+    }
+
+    #[test]
+    fn wrong_stream_length() {
+        assert_every_size(CorruptionKind::WrongStreamLength, |_, problems| {
+            assert!(
+                matches!(problems, [Problem::StreamLength { declared, actual, .. }] if declared != actual),
+                "{problems:?}"
+            );
+        });
+    }
+
+    #[test]
+    fn truncated_xref() {
+        assert_every_size(CorruptionKind::TruncatedXref, |bytes, problems| {
+            assert!(matches!(problems, [Problem::Parse(_)]), "{problems:?}");
+            let xref_start = parse_decimal(&bytes[find_startxref(bytes)]);
+            let xref = String::from_utf8_lossy(&bytes[xref_start..]);
+            let (section, _) = xref.split_once("\nstartxref\n").unwrap();
+            assert!(!section.contains("endstream"), "the cross reference stream is complete");
+            assert!(section.ends_with("    "), "the cut off part is not blanked");
+        });
+    }
+
+    #[test]
+    fn dangling_reference() {
+        assert_every_size(CorruptionKind::DanglingReference, |bytes, problems| {
+            // the object graph is not part of the verification, every object is where it is listed
+            assert_eq!(problems, []);
+            let doc = Document::load_mem(bytes).unwrap();
+            let contents = doc.get_dictionary(first_page(&doc).unwrap()).unwrap().get(b"Contents").unwrap();
+            let contents = contents.as_reference().unwrap();
+            assert!(doc.get_object(contents).is_err(), "object {contents:?} exists");
+        });
+    }
+
+    #[test]
+    fn mismatched_startxref() {
+        assert_every_size(CorruptionKind::MismatchedStartxref, |_, problems| {
+            assert!(matches!(problems, [Problem::Startxref { .. }, Problem::Parse(_)]), "{problems:?}");
+        });
+    }
+
+    #[test]
+    fn duplicate_object_id() {
+        assert_every_size(CorruptionKind::DuplicateObjectId, |bytes, problems| {
+            let [Problem::XrefOffset { id: (number, 0), .. }] = problems else {
+                panic!("{problems:?}");
+            };
+            // the object listed at the offset is missing, the one before it is defined twice there
+            let header = |number: u32| format!("\n{number} 0 obj\n");
+            let text = String::from_utf8_lossy(bytes);
+            assert_eq!(text.matches(&header(*number)).count(), 0);
+            assert_eq!(text.matches(&header(number - 1)).count(), 2);
+        });
+    }
+}
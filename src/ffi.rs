@@ -0,0 +1,120 @@
+//! A C interface, available with the `ffi` feature. The shared library exporting the functions
+//! below is only built on request, with
+//! `cargo rustc --lib --release --features ffi --crate-type cdylib`; `include/generatePDF.h`
+//! declares them for C and C++.
+//!
+//! Every function returns one of the `GENERATEPDF_*` status codes.
+
+use std::ffi::{c_char, c_int, CStr};
+
+use crate::{generate_pdf_into, generate_pdf_with_size, minimum_size, Error, Options};
+
+pub const GENERATEPDF_OK: c_int = 0;
+/// The requested size is smaller than the smallest document which can be generated.
+pub const GENERATEPDF_ERROR_FILE_TOO_SMALL: c_int = 1;
+/// A pointer is null or the path is not valid UTF-8.
+pub const GENERATEPDF_ERROR_INVALID_ARGUMENT: c_int = 2;
+/// Writing the file failed.
+pub const GENERATEPDF_ERROR_IO: c_int = 3;
+/// lopdf failed to build or serialize the document.
+pub const GENERATEPDF_ERROR_PDF: c_int = 4;
+
+/// Fills the `buffer_len` bytes at `buffer` with a document of exactly that size.
+///
+/// # Safety
+///
+/// `buffer` has to be valid for writes of `buffer_len` bytes.
+#[no_mangle]
+pub unsafe extern "C" fn generatepdf_fill_buffer(buffer: *mut u8, buffer_len: usize) -> c_int {
+    if buffer.is_null() {
+        return GENERATEPDF_ERROR_INVALID_ARGUMENT;
+    }
+    status(generate_pdf_into(buffer_len, std::slice::from_raw_parts_mut(buffer, buffer_len)))
+}
+
+/// Saves a document of exactly `file_size_bytes` bytes to the file at `path`, replacing it if it
+/// exists.
+///
+/// # Safety
+///
+/// `path` has to point to a NUL terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn generatepdf_save(path: *const c_char, file_size_bytes: usize) -> c_int {
+    if path.is_null() {
+        return GENERATEPDF_ERROR_INVALID_ARGUMENT;
+    }
+    let Ok(path) = CStr::from_ptr(path).to_str() else {
+        return GENERATEPDF_ERROR_INVALID_ARGUMENT;
+    };
+    status(generate_pdf_with_size(file_size_bytes).and_then(|mut doc| Ok(doc.save(path).map(|_| ())?)))
+}
+
+/// Stores the smallest size a document can be generated with at `minimum`.
+///
+/// # Safety
+///
+/// `minimum` has to be valid for writes.
+#[no_mangle]
+pub unsafe extern "C" fn generatepdf_minimum_size(minimum: *mut usize) -> c_int {
+    if minimum.is_null() {
+        return GENERATEPDF_ERROR_INVALID_ARGUMENT;
+    }
+    status(minimum_size(&Options::default()).map(|size| *minimum = size))
+}
+
+fn status(result: Result<(), Error>) -> c_int {
+    match result {
+        Ok(()) => GENERATEPDF_OK,
+        Err(Error::FileTooSmall { .. }) => GENERATEPDF_ERROR_FILE_TOO_SMALL,
+        Err(Error::IOError(_)) => GENERATEPDF_ERROR_IO,
+        Err(Error::LoPDFError(_)) => GENERATEPDF_ERROR_PDF,
+        Err(Error::InvalidOption(_)) => GENERATEPDF_ERROR_INVALID_ARGUMENT,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::ffi::CString;
+
+    use super::*;
+    use crate::verify::{verify_pdf, verify_pdf_bytes, SizeTarget};
+
+    #[test]
+    fn fills_buffers() {
+        let mut minimum = 0;
+        assert_eq!(unsafe { generatepdf_minimum_size(&mut minimum) }, GENERATEPDF_OK);
+        assert_eq!(minimum, minimum_size(&Options::default()).unwrap());
+
+        for len in [minimum, 10_000] {
+            let mut buffer = vec![0; len];
+            assert_eq!(unsafe { generatepdf_fill_buffer(buffer.as_mut_ptr(), len) }, GENERATEPDF_OK);
+            assert!(verify_pdf_bytes(&buffer, SizeTarget::Exact(len)).is_valid());
+        }
+        let mut buffer = vec![0; minimum - 1];
+        let status = unsafe { generatepdf_fill_buffer(buffer.as_mut_ptr(), buffer.len()) };
+        assert_eq!(status, GENERATEPDF_ERROR_FILE_TOO_SMALL);
+    }
+
+    #[test]
+    fn saves_files() {
+        let path = std::env::temp_dir().join(format!("generatePDF-ffi-{}.pdf", std::process::id()));
+        let c_path = CString::new(path.to_str().unwrap()).unwrap();
+        let status = unsafe { generatepdf_save(c_path.as_ptr(), 10_000) };
+        let report = verify_pdf(&path, SizeTarget::Exact(10_000));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(status, GENERATEPDF_OK);
+        assert!(report.unwrap().is_valid());
+
+        let directory = CString::new(std::env::temp_dir().to_str().unwrap()).unwrap();
+        assert_eq!(unsafe { generatepdf_save(directory.as_ptr(), 10_000) }, GENERATEPDF_ERROR_IO);
+    }
+
+    #[test]
+    fn rejects_null_pointers() {
+        unsafe {
+            assert_eq!(generatepdf_fill_buffer(std::ptr::null_mut(), 10_000), GENERATEPDF_ERROR_INVALID_ARGUMENT);
+            assert_eq!(generatepdf_save(std::ptr::null(), 10_000), GENERATEPDF_ERROR_INVALID_ARGUMENT);
+            assert_eq!(generatepdf_minimum_size(std::ptr::null_mut()), GENERATEPDF_ERROR_INVALID_ARGUMENT);
+        }
+    }
+}
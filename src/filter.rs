@@ -0,0 +1,137 @@
+//! Encoding the content streams holding the padding with the filters of
+//! [`Options::stream_filters`], each exercising another decoder of a reader.
+//!
+//! The encoded data never shrinks as the data grows, so a larger filler still never produces a
+//! smaller document. ASCII85 does not abbreviate groups of zeros as `z`, which keeps the length of
+//! its output a function of the length of its input. Run length encoding on the other hand stores
+//! runs of up to 128 bytes of filler in two bytes, so the filler grows the file by about one byte
+//! every 64 bytes and is decoded to about 64 times the size of the file.
+
+use std::str::FromStr;
+
+use lopdf::Object;
+
+use crate::generator::Scratch;
+use crate::{Error, Options};
+
+/// A filter of the content streams holding the padding, see [`Options::stream_filters`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StreamFilter {
+    /// `ASCIIHexDecode`, two hexadecimal digits for every byte.
+    AsciiHex,
+    /// `ASCII85Decode`, five printable characters for every four bytes.
+    Ascii85,
+    /// `RunLengthDecode`, runs of the same byte and literal sequences of up to 128 bytes each. The
+    /// filler is stored in runs taking two bytes for 128 bytes of it, so it decodes to about 64
+    /// times the size of the file.
+    RunLength,
+}
+
+impl StreamFilter {
+    /// Returns the name of the filter in the `/Filter` entry of a stream.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            StreamFilter::AsciiHex => "ASCIIHexDecode",
+            StreamFilter::Ascii85 => "ASCII85Decode",
+            StreamFilter::RunLength => "RunLengthDecode",
+        }
+    }
+
+    fn encode(&self, data: &[u8], encoded: &mut Vec<u8>) {
+        match self {
+            StreamFilter::AsciiHex => ascii_hex(data, encoded),
+            StreamFilter::Ascii85 => ascii85(data, encoded),
+            StreamFilter::RunLength => run_length(data, encoded),
+        }
+    }
+}
+
+/// Parses the name of a filter in kebab case, e.g. `ascii85`.
+impl FromStr for StreamFilter {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "ascii-hex" => Ok(StreamFilter::AsciiHex),
+            "ascii85" => Ok(StreamFilter::Ascii85),
+            "run-length" => Ok(StreamFilter::RunLength),
+            _ => Err(Error::InvalidOption(format!(
+                "Unknown stream filter {s}, expected ascii-hex, ascii85 or run-length."
+            ))),
+        }
+    }
+}
+
+/// The `/Filter` entry of a stream encoded with `filters`, a name for a single filter.
+pub(crate) fn filter_entry(filters: &[StreamFilter]) -> Object {
+    match filters {
+        [filter] => filter.as_str().into(),
+        _ => Object::Array(filters.iter().map(|filter| filter.as_str().into()).collect()),
+    }
+}
+
+/// Encodes `data` with the filters of `options`. Readers decode with the filters in the order they
+/// are listed, so they are applied the other way round.
+pub(crate) fn encode(mut data: Vec<u8>, options: &Options, scratch: &mut Scratch) -> Vec<u8> {
+    for filter in options.stream_filters.iter().rev() {
+        let mut encoded = scratch.buffer(data.len());
+        filter.encode(&data, &mut encoded);
+        scratch.reclaim_buffer(std::mem::replace(&mut data, encoded));
+    }
+    data
+}
+
+fn ascii_hex(data: &[u8], encoded: &mut Vec<u8>) {
+    const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+    encoded.reserve(2 * data.len() + 1);
+    for byte in data {
+        encoded.extend_from_slice(&[HEX_DIGITS[(byte >> 4) as usize], HEX_DIGITS[(byte & 0xF) as usize]]);
+    }
+    encoded.push(b'>');
+}
+
+fn ascii85(data: &[u8], encoded: &mut Vec<u8>) {
+    encoded.reserve(data.len().div_ceil(4) * 5 + 2);
+    for group in data.chunks(4) {
+        // a final group of fewer bytes is padded with zeros and written with as many digits plus one
+        let mut bytes = [0; 4];
+        bytes[..group.len()].copy_from_slice(group);
+        let mut value = u32::from_be_bytes(bytes);
+        let mut digits = [0; 5];
+        for digit in digits.iter_mut().rev() {
+            *digit = b'!' + (value % 85) as u8;
+            value /= 85;
+        }
+        encoded.extend_from_slice(&digits[..group.len() + 1]);
+    }
+    encoded.extend_from_slice(b"~>");
+}
+
+/// Longest run or literal sequence a single length byte describes.
+const MAX_RUN: usize = 128;
+/// Length byte marking the end of the data.
+const RUN_LENGTH_END: u8 = 128;
+
+fn run_length(data: &[u8], encoded: &mut Vec<u8>) {
+    let mut literal_start = 0;
+    let mut index = 0;
+    let flush_literal = |encoded: &mut Vec<u8>, literal: &[u8]| {
+        for chunk in literal.chunks(MAX_RUN) {
+            encoded.push((chunk.len() - 1) as u8);
+            encoded.extend_from_slice(chunk);
+        }
+    };
+    while index < data.len() {
+        let run = data[index..].iter().take(MAX_RUN).take_while(|&&byte| byte == data[index]).count();
+        if run < 2 {
+            index += 1;
+            continue;
+        }
+        flush_literal(encoded, &data[literal_start..index]);
+        encoded.extend_from_slice(&[(257 - run) as u8, data[index]]);
+        index += run;
+        literal_start = index;
+    }
+    flush_literal(encoded, &data[literal_start..]);
+    encoded.push(RUN_LENGTH_END);
+}
@@ -0,0 +1,206 @@
+//! Generating many documents in a row without allocating the padding from scratch every time.
+//!
+//! Finding the right amount of filler builds and serializes a handful of candidate documents. Only
+//! their sizes matter, so their padding is left as zeros, unless stream filters encode it. The
+//! buffers of zeros are kept apart from the others, so a candidate only has to zero what it needs
+//! beyond the previous one. The buffers of the documents are collected in a [`Scratch`] pool and
+//! handed out again for the padding of the next document, so a [`Generator`] producing documents
+//! of similar sizes mostly reuses memory it already holds.
+
+use lopdf::content::Operation;
+use lopdf::{Dictionary, Document, Object};
+
+use crate::{filler_byte, generate, generate_into_slice, Error, Options};
+
+/// Generates documents with the same options, reusing its buffers between them.
+#[derive(Default)]
+pub struct Generator {
+    options: Options,
+    scratch: Scratch,
+}
+
+impl Generator {
+    pub fn new(options: Options) -> Self {
+        Generator { options, scratch: Scratch::default() }
+    }
+
+    /// Replaces the contents of `output` with a document which is exactly `file_size_bytes` bytes
+    /// large. Passing the same `output` again reuses its allocation as well.
+    pub fn generate_into(&mut self, file_size_bytes: usize, output: &mut Vec<u8>) -> Result<(), Error> {
+        let mut doc = generate(file_size_bytes, &self.options, &mut self.scratch)?;
+        output.clear();
+        #[cfg(feature = "tracing")]
+        let _span = tracing::debug_span!("save", len = file_size_bytes).entered();
+        doc.save_to(output)?;
+        self.scratch.reclaim(doc);
+        Ok(())
+    }
+
+    /// Writes a document which is exactly `file_size_bytes` bytes large into `buf`, which has to be
+    /// just as long, like [`generate_pdf_into_with_options`](crate::generate_pdf_into_with_options).
+    /// The padding is taken from the buffers of the previous documents.
+    pub fn generate_into_slice(&mut self, file_size_bytes: usize, buf: &mut [u8]) -> Result<(), Error> {
+        generate_into_slice(file_size_bytes, &self.options, buf, &mut self.scratch)
+    }
+}
+
+/// Amount of buffers kept for reuse, of those with zeros and the others each. Every document holds
+/// at most a content stream and an image with padding, besides the strings of form fields or
+/// annotations.
+const POOLED_BUFFERS: usize = 4;
+
+/// A pool of buffers for the padding, the largest ones last.
+#[derive(Debug, Default)]
+pub(crate) struct Scratch {
+    buffers: Vec<Vec<u8>>,
+    /// Buffers holding nothing but zeros, for the padding of measured documents.
+    zeros: Vec<Vec<u8>>,
+    /// The decompressed size, the filler byte and the data of the last compressed content stream.
+    compressed: Option<(usize, u8, Vec<u8>)>,
+    /// Whether the documents built are only measured, see [`Scratch::measure`].
+    measuring: bool,
+}
+
+impl Scratch {
+    /// Returns an empty buffer for about `capacity` bytes, reusing the smallest one available which
+    /// holds them, or else the largest one.
+    pub fn buffer(&mut self, capacity: usize) -> Vec<u8> {
+        let mut buffer = take(&mut self.buffers, capacity);
+        buffer.clear();
+        buffer
+    }
+
+    /// Returns a buffer of `len` filler bytes.
+    pub fn filler(&mut self, len: usize, options: &Options) -> Vec<u8> {
+        self.bytes(len, filler_byte(options))
+    }
+
+    /// Returns a buffer of `len` bytes of `value`, or of zeros while measuring. Nothing writes to
+    /// the padding of measured documents, so only the bytes beyond the previous length of a buffer of
+    /// zeros have to be zeroed.
+    pub fn bytes(&mut self, len: usize, value: u8) -> Vec<u8> {
+        if self.measuring {
+            let mut zeros = take(&mut self.zeros, len);
+            if zeros.capacity() < len {
+                // the allocator hands out zeroed memory without touching it
+                return vec![0; len];
+            }
+            zeros.truncate(len);
+            zeros.resize(len, 0);
+            return zeros;
+        }
+        let mut buffer = self.buffer(len);
+        buffer.resize(len, value);
+        buffer
+    }
+
+    /// Calls `measure`, which builds documents only to serialize them into a
+    /// [`CountingSink`](crate::sizing::CountingSink). The padding of these documents has the right
+    /// length, but its bytes may differ, unless stream filters encode them.
+    pub fn measure<T>(&mut self, measure: impl FnOnce(&mut Self) -> T) -> T {
+        let measuring = std::mem::replace(&mut self.measuring, true);
+        let result = measure(self);
+        self.measuring = measuring;
+        result
+    }
+
+    /// Whether the documents built are only measured, see [`Scratch::measure`].
+    pub fn measuring(&self) -> bool {
+        self.measuring
+    }
+
+    /// Returns the data of the compressed content stream decoding to `decompressed_size` bytes of
+    /// `filler`, calling `compress` unless it is the same as last time.
+    pub fn compressed(
+        &mut self,
+        decompressed_size: usize,
+        filler: u8,
+        compress: impl FnOnce() -> Result<Vec<u8>, Error>,
+    ) -> Result<Vec<u8>, Error> {
+        match &self.compressed {
+            Some((size, byte, data)) if (*size, *byte) == (decompressed_size, filler) => Ok(data.clone()),
+            _ => {
+                let data = compress()?;
+                self.compressed = Some((decompressed_size, filler, data.clone()));
+                Ok(data)
+            }
+        }
+    }
+
+    /// Takes back a buffer which is no longer needed.
+    pub fn reclaim_buffer(&mut self, buffer: Vec<u8>) {
+        keep(&mut self.buffers, std::iter::once(buffer));
+    }
+
+    /// Takes back the string operands of content stream operations, once they have been encoded.
+    pub fn reclaim_operations(&mut self, operations: Vec<Operation>) {
+        let operands = operations.into_iter().flat_map(|operation| operation.operands);
+        keep(&mut self.buffers, operands.filter_map(|operand| match operand {
+            Object::String(bytes, _) => Some(bytes),
+            _ => None,
+        }));
+    }
+
+    /// Takes back the stream data and the strings of the dictionaries of a document which is no
+    /// longer needed, which include the padding of form fields and annotations. The padding of a
+    /// measured document goes back to the buffers of zeros.
+    pub fn reclaim(&mut self, doc: Document) {
+        let strings = |dictionary: Dictionary| {
+            dictionary.into_iter().filter_map(|(_, value)| match value {
+                Object::String(bytes, _) => Some(bytes),
+                _ => None,
+            })
+        };
+        let buffers = doc.objects.into_values().flat_map(|object| match object {
+            Object::Stream(stream) => vec![stream.content],
+            Object::Dictionary(dictionary) => strings(dictionary).collect(),
+            _ => Vec::new(),
+        });
+        if !self.measuring {
+            return keep(&mut self.buffers, buffers);
+        }
+        let (zeros, buffers): (Vec<_>, Vec<_>) = buffers.partition(|buffer| is_zeros(buffer));
+        keep(&mut self.zeros, zeros.into_iter());
+        keep(&mut self.buffers, buffers.into_iter());
+    }
+}
+
+/// Whether `bytes` are all zeros, compared a block at a time.
+fn is_zeros(bytes: &[u8]) -> bool {
+    const ZEROS: [u8; 4096] = [0; 4096];
+    bytes.chunks(ZEROS.len()).all(|block| block == &ZEROS[..block.len()])
+}
+
+/// Takes the smallest buffer of `pool` which holds `len` bytes, or the largest one.
+fn take(pool: &mut Vec<Vec<u8>>, len: usize) -> Vec<u8> {
+    match pool.iter().position(|buffer| buffer.capacity() >= len) {
+        Some(index) => pool.remove(index),
+        None => pool.pop().unwrap_or_default(),
+    }
+}
+
+/// Adds `buffers` to `pool`, keeping only the largest ones.
+fn keep(pool: &mut Vec<Vec<u8>>, buffers: impl Iterator<Item = Vec<u8>>) {
+    pool.extend(buffers.filter(|buffer| buffer.capacity() > 0));
+    pool.sort_by_key(Vec::capacity);
+    let excess = pool.len().saturating_sub(POOLED_BUFFERS);
+    pool.drain(..excess);
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{generate_pdf_bytes, minimum_size, Generator, Options, PaddingStrategy};
+
+    #[test]
+    fn fills_slices_like_generate_pdf_bytes() {
+        let options = Options::default().padding(PaddingStrategy::HexText);
+        let mut generator = Generator::new(options.clone());
+        let minimum = minimum_size(&options).unwrap();
+        for size in [minimum, minimum + 1, 10 * minimum, minimum + 2] {
+            let mut buf = vec![0; size];
+            generator.generate_into_slice(size, &mut buf).unwrap();
+            assert_eq!(buf, generate_pdf_bytes(size, &options).unwrap());
+        }
+        assert!(generator.generate_into_slice(minimum, &mut [0; 10]).is_err());
+    }
+}
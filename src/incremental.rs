@@ -0,0 +1,236 @@
+//! Documents made up of several revisions, each appended to the file as an incremental update with
+//! its own cross reference section, trailer and `%%EOF` marker.
+//!
+//! Every update redefines the page content stream, the final one also redefines the images, form
+//! fields and annotations of the [`PaddingStrategy`](crate::PaddingStrategy) and adds any further
+//! content streams the filler is split across. Only the final revision carries the filler, so the
+//! earlier revisions are small and the padding calculation only has to consider the last one. The
+//! compressed stream of [`Options::decompressed_size`] is only defined in the original revision.
+//! Note that lopdf starts every update with another `%PDF-` header line, which readers treat as a
+//! comment.
+
+use lopdf::{Document, IncrementalDocument, ObjectId};
+
+use crate::generator::Scratch;
+use crate::padding::page_content;
+use crate::sizing::{apply_slack, solve, CountingSink, Layout};
+use crate::{
+    build_document, checksum, contents, embed_padding_checksum, first_page, first_page_contents, first_page_streams,
+    generate_pdf_with_options, padding_objects, save_to_vec, trailer_id, validate_lopdf_size, xobject_id, Error,
+    Options,
+};
+
+/// Generates a document with `updates` incremental updates which is exactly `file_size_bytes` bytes
+/// large once saved.
+pub fn generate_incremental_pdf_with_size(file_size_bytes: usize, updates: usize) -> Result<IncrementalDocument, Error> {
+    generate_incremental_pdf_with_options(file_size_bytes, updates, &Options::default())
+}
+
+/// Generates a document like [`generate_incremental_pdf_with_size`], applying `options` to the
+/// original revision.
+pub fn generate_incremental_pdf_with_options(
+    file_size_bytes: usize,
+    updates: usize,
+    options: &Options,
+) -> Result<IncrementalDocument, Error> {
+    if options.signature_placeholder.is_some() && updates > 0 {
+        return Err(Error::InvalidOption("Signature placeholders cannot be combined with incremental updates.".into()));
+    }
+    options.validate_size(file_size_bytes)?;
+    validate_lopdf_size(file_size_bytes)?;
+    if updates == 0 {
+        let new_document = generate_pdf_with_options(file_size_bytes, options)?;
+        // lopdf writes the cross reference section in the format of the previous revision
+        let mut prev = Document::with_version(new_document.version.clone());
+        prev.reference_table.cross_reference_type = new_document.reference_table.cross_reference_type;
+        let mut doc = IncrementalDocument::create_from(Vec::new(), prev);
+        doc.new_document = new_document;
+        return Ok(doc);
+    }
+
+    let mut scratch = Scratch::default();
+    let id = trailer_id(file_size_bytes, options)?;
+    let (prev_bytes, prev) = revision_chain(updates - 1, options, id.as_deref(), &mut scratch)?;
+    let page_id = first_page(&prev)?;
+    let prev_content_ids = first_page_contents(&prev)?;
+    // the compressed stream of `Options::decompressed_size` comes last and is kept as it is
+    let compressed = options.decompressed_size.map_or(0, |_| 1);
+    let (prev_padding_ids, compressed_ids) = prev_content_ids.split_at(prev_content_ids.len() - compressed);
+    let prev_padding = padding_objects(&prev)?;
+
+    let build = |layout: Layout, scratch: &mut Scratch| -> Result<(IncrementalDocument, Vec<ObjectId>), Error> {
+        let mut update = IncrementalDocument::create_from(prev_bytes.clone(), prev.clone());
+        update.new_document.version = prev.version.clone();
+        let page = page_content(options, layout.filler, scratch)?;
+        let mut content_ids = Vec::new();
+        for (index, stream) in first_page_streams(page.contents, options, scratch)?.into_iter().enumerate() {
+            // the content streams of the previous revision are redefined, any further ones are added
+            let content_id = match prev_padding_ids.get(index) {
+                Some(&content_id) => {
+                    update.new_document.set_object(content_id, stream);
+                    content_id
+                }
+                None => update.new_document.add_object(stream),
+            };
+            content_ids.push(content_id);
+        }
+        content_ids.extend(compressed_ids);
+        if content_ids != prev_content_ids {
+            update.opt_clone_object_to_new_document(page_id)?;
+            update.new_document.get_dictionary_mut(page_id)?.set("Contents", contents(&content_ids));
+        }
+        // the images keep their IDs, so the resources do not need to be updated
+        for (name, xobject) in page.xobjects {
+            update.new_document.set_object(xobject_id(&prev, name)?, xobject);
+        }
+        for (&field_id, field) in prev_padding.field_ids.iter().zip(page.fields) {
+            update.new_document.set_object(field_id, field);
+        }
+        let annotations = page.annotations.into_iter().flatten();
+        for (&annotation_id, annotation) in prev_padding.annotation_ids.iter().zip(annotations) {
+            update.new_document.set_object(annotation_id, annotation);
+        }
+        if options.padding_checksum {
+            // the information dictionary is redefined to hold the checksum of the new padding
+            update.opt_clone_object_to_new_document(prev.trailer.get(b"Info")?.as_reference()?)?;
+        }
+        apply_slack(&mut update.new_document.trailer, layout);
+        Ok((update, content_ids))
+    };
+
+    let layout = solve(file_size_bytes, |layout| {
+        scratch.measure(|scratch| {
+            let mut sink = CountingSink::default();
+            let (mut update, _) = build(layout, scratch)?;
+            update.save_to(&mut sink)?;
+            scratch.reclaim(update.new_document);
+            Ok(sink.bytes_written)
+        })
+    })?;
+
+    let (mut update, content_ids) = build(layout, &mut scratch)?;
+    if options.padding_checksum {
+        let info_id = prev.trailer.get(b"Info")?.as_reference()?;
+        // the content streams of the further pages are kept as they are
+        let mut padding = prev_padding;
+        padding.content_ids.splice(..prev_content_ids.len(), content_ids);
+        checksum::embed_in_update(&mut update, info_id, &padding)?;
+    }
+    Ok(update)
+}
+
+/// Serializes an empty document followed by `updates` empty updates. Returns the bytes as well as the
+/// combined document, which is required to append another update.
+fn revision_chain(
+    updates: usize,
+    options: &Options,
+    id: Option<&[u8]>,
+    scratch: &mut Scratch,
+) -> Result<(Vec<u8>, Document), Error> {
+    let mut doc = build_document(options, id, Layout { filler: 0, slack: None }, scratch)?;
+    if options.padding_checksum {
+        embed_padding_checksum(&mut doc)?;
+    }
+    let mut bytes = save_to_vec(&mut doc)?;
+    doc.xref_start = last_startxref(&bytes);
+    let content_ids = first_page_contents(&doc)?;
+
+    for _ in 0..updates {
+        let mut update = IncrementalDocument::create_from(bytes, doc);
+        update.new_document.version = update.get_prev_documents().version.clone();
+        // without filler there are as many content streams as in the original revision
+        let page = page_content(options, 0, scratch)?;
+        for (&content_id, stream) in content_ids.iter().zip(first_page_streams(page.contents, options, scratch)?) {
+            update.new_document.set_object(content_id, stream);
+        }
+
+        let mut next_bytes = Vec::new();
+        update.save_to(&mut next_bytes)?;
+
+        // saving assigned the ID of the cross reference stream and completed the trailer
+        let mut combined = update.get_prev_documents().clone();
+        combined.objects.extend(update.new_document.objects);
+        combined.max_id = update.new_document.max_id;
+        combined.trailer = update.new_document.trailer;
+        combined.xref_start = last_startxref(&next_bytes);
+
+        bytes = next_bytes;
+        doc = combined;
+    }
+
+    Ok((bytes, doc))
+}
+
+/// Parses the offset printed after the final `startxref` keyword.
+fn last_startxref(bytes: &[u8]) -> usize {
+    let offset = bytes.strip_suffix(b"\n%%EOF").expect("lopdf ends every revision with %%EOF");
+    let digits = offset.iter().rev().take_while(|byte| byte.is_ascii_digit()).count();
+    offset[offset.len() - digits..]
+        .iter()
+        .fold(0, |acc, digit| acc * 10 + (digit - b'0') as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::Document;
+
+    use super::generate_incremental_pdf_with_options;
+    use crate::verify::{verify_pdf_bytes, SizeTarget};
+    use crate::{Options, PaddingStrategy};
+
+    /// Saves the document of `updates` updates with `options` for several sizes from `from` on, each
+    /// of which has to come out exactly, and returns the last one.
+    fn assert_exact_sizes(options: &Options, updates: usize, from: usize) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        for size in from..from + 16 {
+            let mut doc = generate_incremental_pdf_with_options(size, updates, options)
+                .unwrap_or_else(|error| panic!("{size} bytes, {updates} updates: {error}"));
+            bytes.clear();
+            doc.save_to(&mut bytes).unwrap();
+            assert_eq!(bytes.len(), size, "{updates} updates");
+            assert!(verify_pdf_bytes(&bytes, SizeTarget::Exact(size)).is_valid(), "{size} bytes, {updates} updates");
+        }
+        bytes
+    }
+
+    #[test]
+    fn every_size_with_every_strategy() {
+        for padding in PaddingStrategy::ALL {
+            let options = Options::default().padding(padding);
+            for updates in [0, 1, 3] {
+                assert_exact_sizes(&options, updates, 100_000);
+            }
+        }
+    }
+
+    #[test]
+    fn every_size_around_digit_boundaries() {
+        let options = Options::default();
+        for updates in [1, 2] {
+            for boundary in [10_000, 100_000] {
+                assert_exact_sizes(&options, updates, boundary - 8);
+            }
+        }
+    }
+
+    #[test]
+    fn every_revision_loads() {
+        for updates in [0, 1, 2, 5] {
+            let bytes = assert_exact_sizes(&Options::default(), updates, 30_000);
+            let ends: Vec<usize> = bytes
+                .windows(b"%%EOF".len())
+                .enumerate()
+                .filter(|(_, window)| *window == b"%%EOF")
+                .map(|(start, window)| start + window.len())
+                .collect();
+            assert_eq!(ends.len(), updates + 1);
+            // every revision is a complete document on its own, the cross reference sections of the
+            // updates link to those of the revisions before them
+            for (revision, &end) in ends.iter().enumerate() {
+                let doc = Document::load_mem(&bytes[..end])
+                    .unwrap_or_else(|error| panic!("revision {revision} of {updates} updates: {error}"));
+                assert_eq!(doc.get_pages().len(), 1, "revision {revision} of {updates} updates");
+            }
+        }
+    }
+}
@@ -1,44 +1,287 @@
+#![allow(non_snake_case)]
+
 use std::fmt::{Display, Formatter};
-use lopdf::{dictionary, Document, Object, Stream, StringFormat};
+use std::io::Write;
+use lopdf::{dictionary, Dictionary, Document, Object, ObjectId, Stream, StringFormat};
+use lopdf::xref::XrefType;
 use lopdf::content::{Content, Operation};
+use sha2::{Digest, Sha256};
+
+#[cfg(feature = "tokio")]
+mod async_writer;
+#[cfg(feature = "batch")]
+pub mod batch;
+#[cfg(feature = "http-body")]
+pub mod body;
+#[cfg(any(feature = "http-body", feature = "tokio"))]
+mod channel;
+pub mod checksum;
+mod compressed;
+pub mod corruption;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod filter;
+mod generator;
+pub mod incremental;
+#[cfg(feature = "native-writer")]
+pub mod native;
+mod options;
+mod outline;
+mod padding;
+#[cfg(feature = "rayon")]
+mod parallel;
+mod plan;
+pub mod progress;
+mod report;
+mod signature;
+mod sizing;
+pub mod sparse;
+pub mod stress;
+mod unicode;
+pub mod verify;
+#[cfg(feature = "wasm")]
+mod wasm;
+mod xmp;
+
+#[cfg(feature = "tokio")]
+pub use async_writer::{generate_pdf_to_async_writer, generate_pdf_to_async_writer_with_options};
+pub use filter::StreamFilter;
+pub use generator::Generator;
+pub use options::{LineEnding, Options, PageSize, PdfVersion, TrailerId, XrefEntryEnding};
+pub use padding::PaddingStrategy;
+#[cfg(feature = "rayon")]
+pub use parallel::{generate_many, generate_many_with_options};
+pub use plan::{plan, plan_with_options, GenerationPlan};
+pub use report::{generate_pdf_bytes_with_report, SizeReport};
+pub use unicode::CjkFont;
+
+use generator::Scratch;
+use padding::ContentStream;
+use progress::ProgressWriter;
+use sizing::{apply_slack, solve, CountingSink, Layout};
 
 #[derive(Debug)]
 pub enum Error {
-    FileTooSmall(usize),
+    FileTooSmall { requested: usize, minimum: usize },
     LoPDFError(lopdf::Error),
+    IOError(std::io::Error),
+    InvalidOption(String),
 }
 impl Display for Error {
     fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
         match self {
-            Error::FileTooSmall(bytes) => {
-                f.write_fmt(format_args!("The requested PDF file may not be smaller than {MIN_SIZE_PDF} bytes due to overhead of the generation process.\
-                You requested {bytes} bytes."))
+            Error::FileTooSmall { requested, minimum } => {
+                f.write_fmt(format_args!("The requested PDF file may not be smaller than {minimum} bytes due to overhead of the generation process. \
+                You requested {requested} bytes."))
             }
             Error::LoPDFError(e) => {
                 e.fmt(f)
             }
+            Error::IOError(e) => {
+                e.fmt(f)
+            }
+            Error::InvalidOption(message) => {
+                f.write_str(message)
+            }
         }
     }
 }
 
+impl std::error::Error for Error {}
+
 impl From<lopdf::Error> for Error {
     fn from(value: lopdf::Error) -> Self {
         Error::LoPDFError(value)
     }
 }
 
-const MIN_SIZE_PDF: usize = 544;
+impl From<std::io::Error> for Error {
+    fn from(value: std::io::Error) -> Self {
+        Error::IOError(value)
+    }
+}
 
 pub fn generate_pdf_with_size(file_size_bytes: usize) -> Result<Document, Error> {
-    if file_size_bytes < MIN_SIZE_PDF {
-        return Err(Error::FileTooSmall(file_size_bytes));
+    generate_pdf_with_options(file_size_bytes, &Options::default())
+}
+
+pub fn generate_pdf_with_options(file_size_bytes: usize, options: &Options) -> Result<Document, Error> {
+    generate(file_size_bytes, options, &mut Scratch::default())
+}
+
+/// Returns the size from which on documents of every size can be generated with `options`, which is
+/// a multiple of the [block size](Options::block_size) if there is one.
+pub fn minimum_size(options: &Options) -> Result<usize, Error> {
+    let id = trailer_id(0, options)?;
+    let smallest = smallest_size(options)?;
+    let reachable = sizing::smallest_target(smallest, |file_size_bytes, layout| {
+        measure(options, id.as_deref(), file_size_bytes, layout, &mut Scratch::default())
+    })?;
+    Ok(options.align(reachable))
+}
+
+/// Returns the size of the smallest document built with `options`, regardless of the block size.
+/// Some sizes above it may be skipped, see [`sizing::smallest_target`].
+#[cfg_attr(feature = "tracing", tracing::instrument(name = "overhead", level = "debug", skip_all, ret))]
+pub(crate) fn smallest_size(options: &Options) -> Result<usize, Error> {
+    let id = trailer_id(0, options)?;
+    let layout = Layout { filler: 0, slack: None };
+    let size_of = |file_size_bytes| measure(options, id.as_deref(), file_size_bytes, layout, &mut Scratch::default());
+    // the byte range of a signature ends at the size of the file, so larger files may have a larger
+    // overhead, which converges as soon as the size accounts for its own digits
+    let mut minimum = size_of(0)?;
+    loop {
+        let size = size_of(minimum)?;
+        if size == minimum {
+            return Ok(minimum);
+        }
+        minimum = size;
+    }
+}
+
+/// Generates a document like [`generate_pdf_with_options`] and returns it serialized. Unlike saving
+/// the document to a file, this works on any target, including WebAssembly, and the size can be
+/// checked with [`verify_pdf_bytes`](verify::verify_pdf_bytes) without any disk I/O.
+pub fn generate_pdf_bytes(file_size_bytes: usize, options: &Options) -> Result<Vec<u8>, Error> {
+    save_to_vec(&mut generate_pdf_with_options(file_size_bytes, options)?)
+}
+
+/// Writes a document of exactly `file_size_bytes` bytes into `buf`, which has to be just as long.
+pub fn generate_pdf_into(file_size_bytes: usize, buf: &mut [u8]) -> Result<(), Error> {
+    generate_pdf_into_with_options(file_size_bytes, &Options::default(), buf)
+}
+
+/// Generates a document like [`generate_pdf_with_options`] and writes it into `buf`, which has to be
+/// exactly `file_size_bytes` bytes long. Only the output is not allocated: the document is still
+/// built in memory, along with the candidates measured to find its layout.
+/// [`Generator::generate_into_slice`] reuses their padding between calls, so only the small objects
+/// around it are allocated once it has generated a document of that size.
+pub fn generate_pdf_into_with_options(file_size_bytes: usize, options: &Options, buf: &mut [u8]) -> Result<(), Error> {
+    generate_into_slice(file_size_bytes, options, buf, &mut Scratch::default())
+}
+
+fn generate_into_slice(
+    file_size_bytes: usize,
+    options: &Options,
+    buf: &mut [u8],
+    scratch: &mut Scratch,
+) -> Result<(), Error> {
+    if buf.len() != file_size_bytes {
+        return Err(Error::InvalidOption(format!(
+            "The buffer is {} bytes long, a document of {file_size_bytes} bytes was requested.",
+            buf.len()
+        )));
     }
+    let mut doc = generate(file_size_bytes, options, scratch)?;
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("save", len = file_size_bytes).entered();
+    // a slice is written from the front, a document exceeding it fails with `WriteZero`
+    doc.save_to(&mut &mut buf[..])?;
+    scratch.reclaim(doc);
+    Ok(())
+}
+
+/// Generates a document like [`generate_pdf_with_options`] and writes it to `target`, calling
+/// `progress` with the amount of bytes written so far and `file_size_bytes` while doing so. It is
+/// called with 0 bytes before the document is generated, which takes a while for large documents
+/// as well.
+pub fn write_pdf_with_progress(
+    file_size_bytes: usize,
+    options: &Options,
+    target: impl Write,
+    progress: impl FnMut(usize, usize),
+) -> Result<(), Error> {
+    let mut target = ProgressWriter::new(target, file_size_bytes, progress);
+    target.report();
+    let mut doc = generate_pdf_with_options(file_size_bytes, options)?;
+    #[cfg(feature = "tracing")]
+    let _span = tracing::debug_span!("save", len = file_size_bytes).entered();
+    doc.save_to(&mut target)?;
+    Ok(target.flush()?)
+}
 
-    let mut buffer: Vec<u8> = vec![0; file_size_bytes - calculate_overhead(file_size_bytes)];
-    fill(&mut buffer);
+fn generate(file_size_bytes: usize, options: &Options, scratch: &mut Scratch) -> Result<Document, Error> {
+    Ok(generate_with_layout(file_size_bytes, options, scratch)?.0)
+}
+
+/// Generates a document like [`generate`] and also returns the layout it was built from.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "generate",
+        level = "debug",
+        skip_all,
+        fields(file_size_bytes = file_size_bytes, padding = ?options.padding),
+    )
+)]
+fn generate_with_layout(
+    file_size_bytes: usize,
+    options: &Options,
+    scratch: &mut Scratch,
+) -> Result<(Document, Layout), Error> {
+    let (id, layout) = solve_layout(file_size_bytes, options, scratch)?;
+    Ok((build_solved(file_size_bytes, options, id.as_deref(), layout, scratch)?, layout))
+}
+
+/// Solves for the layout of a document like [`generate`] builds and returns it along with the file
+/// identifier. The candidates are only measured, so this fails early and cheaply if the document
+/// cannot be generated.
+pub(crate) fn solve_layout(
+    file_size_bytes: usize,
+    options: &Options,
+    scratch: &mut Scratch,
+) -> Result<(Option<Vec<u8>>, Layout), Error> {
+    options.validate_size(file_size_bytes)?;
+    validate_lopdf_size(file_size_bytes)?;
+    let id = trailer_id(file_size_bytes, options)?;
+    let layout = solve(file_size_bytes, |layout| measure(options, id.as_deref(), file_size_bytes, layout, scratch));
+    match layout {
+        // the solver only knows the smallest document, not the size from which on all are reachable
+        Err(Error::FileTooSmall { requested, .. }) => {
+            Err(Error::FileTooSmall { requested, minimum: minimum_size(options)? })
+        }
+        layout => Ok((id, layout?)),
+    }
+}
+
+/// Builds the document of `file_size_bytes` bytes from a layout and file identifier found by
+/// [`solve_layout`].
+pub(crate) fn build_solved(
+    file_size_bytes: usize,
+    options: &Options,
+    id: Option<&[u8]>,
+    layout: Layout,
+    scratch: &mut Scratch,
+) -> Result<Document, Error> {
+    let mut doc = build_document(options, id, layout, scratch)?;
+    signature::set_byte_range(&mut doc, file_size_bytes)?;
+    if options.padding_checksum {
+        embed_padding_checksum(&mut doc)?;
+    }
+    Ok(doc)
+}
+
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(
+        name = "assemble",
+        level = "trace",
+        skip_all,
+        fields(filler = layout.filler, slack = layout.slack),
+    )
+)]
+fn build_document(options: &Options, id: Option<&[u8]>, layout: Layout, scratch: &mut Scratch) -> Result<Document, Error> {
+    let page = padding::page_content(options, layout.filler, scratch)?;
+
+    options.validate()?;
+    options.validate_lopdf()?;
 
     // `with_version` specifes the PDF version this document complies with.
-    let mut doc = Document::with_version("1.5");
+    let mut doc = Document::with_version(options.pdf_version.as_str());
+    if options.pdf_version < PdfVersion::V1_5 {
+        // cross reference streams were introduced with PDF 1.5
+        doc.reference_table.cross_reference_type = XrefType::CrossReferenceTable;
+    }
     // Object IDs are used for cross referencing in PDF documents.
     // `lopdf` helps keep track of them for us. They are simple integers.
     // Calls to `doc.new_object_id` and `doc.add_object` return an object ID.
@@ -46,6 +289,14 @@ pub fn generate_pdf_with_size(file_size_bytes: usize) -> Result<Document, Error>
     // "Pages" is the root node of the page tree.
     let pages_id = doc.new_object_id();
 
+    // The signature comes right after it, so it can be located without the padding, see
+    // `signature::set_byte_range`.
+    let signature_id = options.signature_placeholder.map(|len| {
+        let signature_id = doc.new_object_id();
+        doc.objects.insert(signature_id, Object::Dictionary(signature::placeholder(len)));
+        signature_id
+    });
+
     // Fonts are dictionaries. The "Type", "Subtype" and "BaseFont" tags
     // are straight out of the PDF spec.
     //
@@ -68,7 +319,7 @@ pub fn generate_pdf_with_size(file_size_bytes: usize) -> Result<Document, Error>
     // Resource dictionaries can contain more than just fonts,
     // but normally just contains fonts.
     // Only one resource dictionary is allowed per page tree root.
-    let resources_id = doc.add_object(dictionary! {
+    let mut resources = dictionary! {
         // Fonts are actually triplely nested dictionaries. Fun!
         "Font" => dictionary! {
             // F1 is the font name used when writing text.
@@ -76,52 +327,75 @@ pub fn generate_pdf_with_size(file_size_bytes: usize) -> Result<Document, Error>
             // have to be F1
             "F1" => font_id,
         },
-    });
-
-    // `Content` is a wrapper struct around an operations struct that contains
-    // a vector of operations. The operations struct contains a vector of
-    // that match up with a particular PDF operator and operands.
-    // Refer to the PDF spec for more details on the operators and operands
-    // Note, the operators and operands are specified in a reverse order
-    // from how they actually appear in the PDF file itself.
-    let content = Content {
-        operations: vec![
-            // BT begins a text element. It takes no operands.
-            Operation::new("BT", vec![]),
-            // Tf specifies the font and font size.
-            // Font scaling is complicated in PDFs.
-            // Refer to the spec for more info.
-            // The `into()` methods convert the types into
-            // an enum that represents the basic object types in PDF documents.
-            Operation::new("Tf", vec!["F1".into(), 0.into()]),
-            // Td adjusts the translation components of the text matrix.
-            // When used for the first time after BT, it sets the initial
-            // text position on the page.
-            // Note: PDF documents have Y=0 at the bottom. Thus 600 to print text near the top.
-            Operation::new("Td", vec![100.into(), 600.into()]),
-            // Tj prints a string literal to the page. By default, this is black text that is
-            // filled in. There are other operators that can produce various textual effects and
-            // colors
-            Operation::new("Tj", vec![Object::String(buffer, StringFormat::Literal)]),
-            // ET ends the text element.
-            Operation::new("ET", vec![]),
-        ],
     };
 
-    // Streams are a dictionary followed by a (possibly encoded) sequence of bytes.
-    // What that sequence of bytes represents, depends on the context.
-    // The stream dictionary is set internally by lopdf and normally doesn't
-    // need to be manually manipulated. It contains keys such as
-    // Length, Filter, DecodeParams, etc.
-    let content_id = doc.add_object(Stream::new(dictionary! {}, content.encode()?));
-
-    // Page is a dictionary that represents one page of a PDF file.
-    // Its required fields are "Type", "Parent" and "Contents".
-    let page_id = doc.add_object(dictionary! {
-        "Type" => "Page",
-        "Parent" => pages_id,
-        "Contents" => content_id,
-    });
+    // The caption is printed in a proportional font, which is more
+    // pleasant to read. Its encoding covers Latin-1.
+    if let Some(caption) = &options.caption {
+        let caption_font_id = if let Some(font) = options.unicode_caption {
+            unicode::add_font(&mut doc, caption, font)
+        } else {
+            doc.add_object(dictionary! {
+                "Type" => "Font",
+                "Subtype" => "Type1",
+                "BaseFont" => "Helvetica",
+                "Encoding" => "WinAnsiEncoding",
+            })
+        };
+        resources.get_mut(b"Font")?.as_dict_mut()?.set(padding::CAPTION_FONT, caption_font_id);
+    }
+
+    // Images are external objects (XObjects) which the content stream draws by name.
+    if !page.xobjects.is_empty() {
+        let mut xobjects = Dictionary::new();
+        for (name, xobject) in page.xobjects {
+            xobjects.set(name, doc.add_object(xobject));
+        }
+        resources.set("XObject", xobjects);
+    }
+    let resources_id = doc.add_object(resources);
+
+    let mut content_ids = Vec::new();
+    for stream in first_page_streams(page.contents, options, scratch)? {
+        content_ids.push(doc.add_object(stream));
+    }
+    // The compressed stream comes last, so the content streams of the padding keep their positions.
+    if let Some(decompressed_size) = options.decompressed_size {
+        content_ids.push(doc.add_object(compressed::content_stream(decompressed_size, options, scratch)?));
+    }
+
+    // Form fields are widget annotations of the page at the same time.
+    let mut field_ids: Vec<ObjectId> = page.fields.into_iter().map(|field| doc.add_object(field)).collect();
+    let text_fields = !field_ids.is_empty();
+    if let Some(signature_id) = signature_id {
+        field_ids.push(doc.add_object(signature::field(signature_id)));
+    }
+    let mut annotations = page.annotations.into_iter();
+
+    let mut page_ids = Vec::new();
+    for index in 0..=options.blank_pages {
+        // Page is a dictionary that represents one page of a PDF file.
+        // Its required fields are "Type", "Parent" and "Contents".
+        // Any further pages are blank, a page without contents is valid.
+        let mut page = dictionary! {
+            "Type" => "Page",
+            "Parent" => pages_id,
+        };
+        let mut annotation_ids = Vec::new();
+        if index == 0 {
+            page.set("Contents", contents(&content_ids));
+            annotation_ids.extend(&field_ids);
+        } else if let Some(&len) = options.page_content_sizes.get(index) {
+            let content = padding::sized_content(Vec::new(), len, index, options)?;
+            page.set("Contents", doc.add_object(Stream::new(dictionary! {}, content)));
+        }
+        let page_annotations = annotations.next().unwrap_or_default();
+        annotation_ids.extend(page_annotations.into_iter().map(|annotation| doc.add_object(annotation)));
+        if !annotation_ids.is_empty() {
+            page.set("Annots", references(&annotation_ids));
+        }
+        page_ids.push(doc.add_object(page));
+    }
 
     // Again, "Pages" is the root of the page tree. The ID was already created
     // at the top of the page, since we needed it to assign to the parent element
@@ -131,19 +405,20 @@ pub fn generate_pdf_with_size(file_size_bytes: usize) -> Result<Document, Error>
     // There are also many additional entries that can be added to the dictionary,
     // if needed. Some of these can also be defined on the page dictionary itself,
     // and not inherited from the page tree root.
+    let (width, height) = options.page_size.dimensions();
     let pages = dictionary! {
         // Type of dictionary
         "Type" => "Pages",
         // Vector of page IDs in document. Normally would contain more than one ID
         // and be produced using a loop of some kind.
-        "Kids" => vec![page_id.into()],
+        "Kids" => references(&page_ids),
         // Page count
-        "Count" => 1,
+        "Count" => page_ids.len() as i64,
         // ID of resources dictionary, defined earlier
         "Resources" => resources_id,
         // A rectangle that defines the boundaries of the physical or digital media.
         // This is the "page size".
-        "MediaBox" => vec![0.into(), 0.into(), 595.into(), 842.into()],
+        "MediaBox" => vec![0.into(), 0.into(), width.into(), height.into()],
     };
 
     // Using `insert()` here, instead of `add_object()` since the ID is already known.
@@ -151,63 +426,434 @@ pub fn generate_pdf_with_size(file_size_bytes: usize) -> Result<Document, Error>
 
     // Creating document catalog.
     // There are many more entries allowed in the catalog dictionary.
-    let catalog_id = doc.add_object(dictionary! {
+    let mut catalog = dictionary! {
         "Type" => "Catalog",
         "Pages" => pages_id,
-    });
+    };
+
+    // The interactive form lists the fields, which are displayed in the font of the hidden text.
+    // Viewers are asked to lay out the values of text fields, as they come without appearance
+    // streams. Regenerating appearances would break signatures though.
+    if !field_ids.is_empty() {
+        let mut form = dictionary! {
+            "Fields" => references(&field_ids),
+            "DA" => Object::string_literal(padding::FIELD_APPEARANCE),
+            "DR" => dictionary! { "Font" => dictionary! { "F1" => font_id } },
+        };
+        if text_fields {
+            form.set("NeedAppearances", true);
+        }
+        if signature_id.is_some() {
+            signature::flag_form(&mut form);
+        }
+        catalog.set("AcroForm", form);
+    }
+
+    // The outline is a tree of bookmarks, each linked to its parent and its siblings.
+    if options.bookmarks > 0 {
+        let outline_id = outline::add_outline(&mut doc, options.bookmarks, options.bookmark_depth, &page_ids);
+        catalog.set("Outlines", outline_id);
+        // viewers open the navigation pane with the outline
+        catalog.set("PageMode", "UseOutlines");
+    }
+
+    // JavaScript actions are run when the document is opened, the document-level scripts are
+    // listed in the JavaScript name tree.
+    if options.benign_javascript {
+        let script = |script: &str| dictionary! { "S" => "JavaScript", "JS" => Object::string_literal(script) };
+        let document_script_id = doc.add_object(script(DOCUMENT_SCRIPT));
+        catalog.set("Names", dictionary! {
+            "JavaScript" => dictionary! {
+                "Names" => vec![Object::string_literal(DOCUMENT_SCRIPT_NAME), document_script_id.into()],
+            },
+        });
+        catalog.set("OpenAction", script(OPEN_ACTION_SCRIPT));
+    }
+
+    // XMP metadata is an uncompressed XML stream referenced from the catalog.
+    // PDF 2.0 deprecates the information dictionary apart from the dates, so the other entries are
+    // only stored as XMP metadata.
+    let modern = options.pdf_version >= PdfVersion::V2_0;
+    if options.xmp_metadata || (modern && options.info.has_descriptive_entries()) {
+        let metadata_id = doc.add_object(Stream::new(
+            dictionary! { "Type" => "Metadata", "Subtype" => "XML" },
+            xmp::packet(&options.info),
+        ));
+        catalog.set("Metadata", metadata_id);
+    }
+    // The nested objects are patched over a string of the same length once the document is saved.
+    if let Some(len) = options.nesting_placeholder {
+        catalog.set(stress::NESTED_KEY, stress::placeholder(len));
+    }
+    let catalog_id = doc.add_object(catalog);
 
     // The "Root" key in trailer is set to the ID of the document catalog,
     // the remainder of the trailer is set during `doc.save()`.
     doc.trailer.set("Root", catalog_id);
 
+    // The document information dictionary holds metadata such as the title and author.
+    let mut info = if modern { options.info.dates().to_dictionary() } else { options.info.to_dictionary() };
+    if options.padding_checksum {
+        // the checksum can only be calculated once the padding is final
+        info.get_or_insert_with(Dictionary::new).set(checksum::CHECKSUM_KEY, checksum::placeholder());
+    }
+    if let Some(info) = info {
+        let info_id = doc.add_object(info);
+        doc.trailer.set("Info", info_id);
+    }
+
+    // The file identifier consists of the ID of the original file and that of the current
+    // version, which are the same for a new document.
+    if let Some(id) = id {
+        let id = Object::String(id.to_vec(), StringFormat::Hexadecimal);
+        doc.trailer.set("ID", vec![id.clone(), id]);
+    }
+    apply_slack(&mut doc.trailer, layout);
+
     Ok(doc)
 }
 
-fn fill(bytes: &mut [u8]) {
-    bytes.fill_with(|| "4".as_bytes()[0])
-}
-
-// this base overhead was measured manually.
-// It is the overhead outside of the content stream before as well as after the stream
-const BASE_OVERHEAD: usize = 539;
-// the pdf wastes 31 bytes on encapsulating the content stream which we have to consider
-const CONTENT_OVERHEAD: usize = 31;
-const XREF_TABLE_BASE_OFFSET: usize = 162;
-
-/// The overhead is dynamic based on the size of bytes we want to write because
-/// 1. they are counted to produce a length of the content stream - len(content)
-/// 2. an offset to the xref table, which follows the content, is calculated which depends on the
-///     length of the content stream and the length written from case 1
-///
-/// The bytes that need to be filled (len(fill)) are:\
-/// let len(doc) be the desired amount of bytes of the output file and \
-/// let OVERHEAD be 539 (normal document structure) and \
-/// let CONTENT_OVERHEAD be 31 (an overhead which is counted in addition to the len(fill) for case 1) and \
-/// let OFFSET be 162 (start of xref table from end of document) then \
-/// `len(fill) = len(doc) - OVERHEAD - strLen(len(doc) - OFFSET) - strLen(len(doc) - OVERHEAD + CONTENT_OVERHEAD)` \
-/// with strLen(number) = ilog_10(number) + 1
-///
-/// strLen(len(doc) - OFFSET) is the number printed for the XREF table offset \
-/// strLen(len(doc) - OVERHEAD + CONTENT_OVERHEAD) is the number printed for len(fill)\
-/// One could also say\
-/// `len(fill) = len(doc) - OVERHEAD - strLen(xref_table_offset) - strLen(len(fill)) `
-///
-/// and the overhead therefore is: \
-/// `overhead = OVERHEAD + strLen(len(doc) - OVERHEAD + CONTENT_OVERHEAD) + strLen(len(doc) - OFFSET)`
-fn calculate_overhead(bytes: usize) -> usize {
-    BASE_OVERHEAD + calculate_overhead_from_start(bytes) + calculate_overhead_from_end(bytes)
-}
-
-fn calculate_overhead_from_start(bytes: usize) -> usize {
-    let mut content_bytes = bytes - BASE_OVERHEAD + CONTENT_OVERHEAD;
-    let content_bytes_digits = (content_bytes.ilog10() as usize + 1);
-    content_bytes = content_bytes - content_bytes_digits;
-    content_bytes.ilog10() as usize + 1
-}
-
-fn calculate_overhead_from_end(bytes: usize) -> usize {
-    let offset = bytes - XREF_TABLE_BASE_OFFSET;
-    let offset_length = offset.ilog10() as usize + 1;
-
-    offset_length
+/// The harmless scripts of [`Options::benign_javascript`].
+const OPEN_ACTION_SCRIPT: &str = "app.alert(\"generatePDF test fixture: harmless open action\");";
+const DOCUMENT_SCRIPT: &str = "app.alert(\"generatePDF test fixture: harmless document-level script\");";
+const DOCUMENT_SCRIPT_NAME: &str = "generatePDF fixture";
+
+/// The operators of the hidden text around its string, as they are encoded:
+/// - `BT` and `ET` begin and end a text element
+/// - `Tf` selects the font at size 0, which makes the text invisible
+/// - `Td` moves to the position of the text. PDF documents have Y=0 at the bottom, so 600 is near
+///   the top.
+/// - `Tj` shows the string
+pub(crate) const HIDDEN_TEXT_START: &[u8] = b"BT\n/F1 0 Tf\n100 600 Td\n(";
+pub(crate) const HIDDEN_TEXT_END: &[u8] = b") Tj\nET";
+/// The operators around a hexadecimal string of hidden text.
+const HEX_HIDDEN_TEXT_START: &[u8] = b"BT\n/F1 0 Tf\n100 600 Td\n<";
+const HEX_HIDDEN_TEXT_END: &[u8] = b"> Tj\nET";
+
+/// The `/Contents` of a page, which is either a single stream or an array of streams that readers
+/// concatenate.
+pub(crate) fn contents(content_ids: &[ObjectId]) -> Object {
+    match content_ids {
+        [content_id] => Object::Reference(*content_id),
+        _ => references(content_ids),
+    }
+}
+
+fn references(ids: &[ObjectId]) -> Object {
+    Object::Array(ids.iter().map(|id| Object::Reference(*id)).collect())
+}
+
+/// Creates the content streams of the first page. The single stream of the strategies keeping the
+/// filler out of the content streams is completed to the size requested by
+/// [`Options::page_content_sizes`].
+pub(crate) fn first_page_streams(
+    contents: Vec<ContentStream>,
+    options: &Options,
+    scratch: &mut Scratch,
+) -> Result<Vec<Stream>, Error> {
+    let mut streams = Vec::with_capacity(contents.len());
+    for content in contents {
+        streams.push(content_stream(content, options, scratch)?);
+    }
+    if let Some(&len) = options.page_content_sizes.first() {
+        let content = std::mem::take(&mut streams[0].content);
+        streams[0].set_content(padding::sized_content(content, len, 0, options)?);
+    }
+    Ok(streams)
+}
+
+fn content_stream(content: ContentStream, options: &Options, scratch: &mut Scratch) -> Result<Stream, Error> {
+    // The operations of a content stream match up with a particular PDF operator and operands.
+    // Refer to the PDF spec for more details on the operators and operands
+    // Note, the operators and operands are specified in a reverse order
+    // from how they actually appear in the PDF file itself.
+
+    // Streams are a dictionary followed by a (possibly encoded) sequence of bytes.
+    // What that sequence of bytes represents, depends on the context.
+    // The stream dictionary is set internally by lopdf and normally doesn't
+    // need to be manually manipulated. It contains keys such as
+    // Length, Filter, DecodeParams, etc.
+    let encoded = encode_content(&content, options, scratch)?;
+    scratch.reclaim_operations(content.operations);
+    if options.stream_filters.is_empty() {
+        return Ok(Stream::new(dictionary! {}, encoded));
+    }
+    let dict = dictionary! { "Filter" => filter::filter_entry(&options.stream_filters) };
+    Ok(Stream::new(dict, filter::encode(encoded, options, scratch)))
+}
+
+/// Returns the string shown by an operation like `Tj`, unless it is a literal string which needs
+/// escaping.
+fn string_operand(operation: &Operation) -> Option<(&[u8], StringFormat)> {
+    match operation.operands.as_slice() {
+        [Object::String(text, StringFormat::Hexadecimal)] => Some((text, StringFormat::Hexadecimal)),
+        [Object::String(text, StringFormat::Literal)]
+            if !text.iter().any(|byte| matches!(byte, b'(' | b')' | b'\\' | b'\r')) =>
+        {
+            Some((text, StringFormat::Literal))
+        }
+        _ => None,
+    }
+}
+
+/// Encodes `content` like [`Content::encode`] into a buffer of the pool, with the string of the
+/// hidden text written straight into it. The filler is neither copied nor formatted one byte at a
+/// time like lopdf does for hexadecimal strings, which takes minutes for large amounts of filler.
+fn encode_content(content: &ContentStream, options: &Options, scratch: &mut Scratch) -> Result<Vec<u8>, Error> {
+    let Some(hidden_text) = &content.hidden_text else {
+        let mut encoded = scratch.buffer(0);
+        write_operations(&content.operations, &mut encoded)?;
+        return Ok(encoded);
+    };
+    let (before, after) = content.operations.split_at(hidden_text.index);
+    let (text_start, text_end, string_len) = match hidden_text.format {
+        StringFormat::Hexadecimal => (HEX_HIDDEN_TEXT_START, HEX_HIDDEN_TEXT_END, 2 * hidden_text.len),
+        StringFormat::Literal => (HIDDEN_TEXT_START, HIDDEN_TEXT_END, hidden_text.len),
+    };
+    let mut end = text_end.to_vec();
+    if !after.is_empty() {
+        end.push(b'\n');
+        write_operations(after, &mut end)?;
+    }
+
+    // measured documents only need the length of the data, unless stream filters encode it
+    if scratch.measuring() && options.stream_filters.is_empty() {
+        let mut start = Vec::new();
+        write_operations(before, &mut start)?;
+        let separator = usize::from(!before.is_empty());
+        return Ok(scratch.bytes(start.len() + separator + text_start.len() + string_len + end.len(), 0));
+    }
+
+    let mut encoded = scratch.buffer(text_start.len() + string_len + end.len());
+    write_operations(before, &mut encoded)?;
+    if !before.is_empty() {
+        encoded.push(b'\n');
+    }
+    encoded.extend_from_slice(text_start);
+    // the filler byte and the digits formatting it never need escaping
+    let string_start = encoded.len();
+    let byte = filler_byte(options);
+    encoded.reserve(string_len + end.len());
+    match hidden_text.format {
+        StringFormat::Hexadecimal => {
+            encoded.resize(string_start + string_len, 0);
+            for digits in encoded[string_start..].chunks_exact_mut(2) {
+                digits.copy_from_slice(&hex_digits(byte));
+            }
+        }
+        StringFormat::Literal => encoded.resize(string_start + string_len, byte),
+    }
+    encoded.extend_from_slice(&end);
+    Ok(encoded)
+}
+
+/// Appends `operations` encoded like [`Content::encode`] to `encoded`. Operations showing a string
+/// are written here, lopdf copies strings into a buffer grown step by step.
+fn write_operations(operations: &[Operation], encoded: &mut Vec<u8>) -> Result<(), Error> {
+    for (index, operation) in operations.iter().enumerate() {
+        if index > 0 {
+            encoded.push(b'\n');
+        }
+        match string_operand(operation) {
+            Some((text, StringFormat::Hexadecimal)) => {
+                encoded.push(b'<');
+                encoded.extend(text.iter().flat_map(|&byte| hex_digits(byte)));
+                encoded.extend_from_slice(b"> ");
+            }
+            Some((text, StringFormat::Literal)) => {
+                encoded.push(b'(');
+                encoded.extend_from_slice(text);
+                encoded.extend_from_slice(b") ");
+            }
+            None => {
+                encoded.extend(Content { operations: vec![operation.clone()] }.encode()?);
+                continue;
+            }
+        }
+        encoded.extend_from_slice(operation.operator.as_bytes());
+    }
+    Ok(())
+}
+
+/// Formats a byte as two uppercase hexadecimal digits like lopdf.
+fn hex_digits(byte: u8) -> [u8; 2] {
+    const HEX_DIGITS: &[u8; 16] = b"0123456789ABCDEF";
+    [HEX_DIGITS[(byte >> 4) as usize], HEX_DIGITS[(byte & 0xF) as usize]]
+}
+
+pub(crate) fn first_page(doc: &Document) -> Result<ObjectId, Error> {
+    Ok(*doc.get_pages().get(&1).ok_or(lopdf::Error::PageNumberNotFound(1))?)
+}
+
+/// Returns the file identifier selected by [`Options::trailer_id`]. The automatic one is derived from
+/// the requested size and the document without any padding, which reflects all the options.
+pub(crate) fn trailer_id(file_size_bytes: usize, options: &Options) -> Result<Option<Vec<u8>>, Error> {
+    match &options.trailer_id {
+        TrailerId::Automatic if options.deterministic || options.pdf_version >= PdfVersion::V2_0 => {}
+        TrailerId::Automatic | TrailerId::Omitted => return Ok(None),
+        TrailerId::Fixed(id) => return Ok(Some(id.clone())),
+        TrailerId::Seeded(seed) => return Ok(Some(Sha256::digest(seed.to_be_bytes())[..16].to_vec())),
+    }
+    let layout = Layout { filler: 0, slack: None };
+    let skeleton = save_to_vec(&mut build_document(options, None, layout, &mut Scratch::default())?)?;
+    let hash = Sha256::new()
+        .chain_update((file_size_bytes as u64).to_be_bytes())
+        .chain_update(skeleton)
+        .finalize();
+    Ok(Some(hash[..16].to_vec()))
+}
+
+/// Replaces the checksum placeholder of a document built by [`build_document`].
+pub(crate) fn embed_padding_checksum(doc: &mut Document) -> Result<(), Error> {
+    let info_id = doc.trailer.get(b"Info")?.as_reference()?;
+    let padding = padding_objects(doc)?;
+    checksum::embed(doc, info_id, &padding)
+}
+
+/// Looks up the objects holding the padding of a document built by [`build_document`].
+pub(crate) fn padding_objects(doc: &Document) -> Result<checksum::PaddingObjects, Error> {
+    Ok(checksum::PaddingObjects {
+        content_ids: page_contents(doc)?,
+        xobject_ids: xobject_ids(doc)?,
+        field_ids: form_field_ids(doc)?,
+        annotation_ids: annotation_ids(doc)?,
+    })
+}
+
+/// Returns the IDs of all XObjects in the resources of the page tree root, ordered by name.
+pub(crate) fn xobject_ids(doc: &Document) -> Result<Vec<ObjectId>, Error> {
+    let pages = doc.catalog()?.get(b"Pages")?.as_reference()?;
+    let resources = doc.get_dictionary(pages)?.get(b"Resources")?.as_reference()?;
+    let Ok(xobjects) = doc.get_dictionary(resources)?.get(b"XObject") else {
+        return Ok(Vec::new());
+    };
+    let mut xobjects: Vec<_> = xobjects.as_dict()?.iter().collect();
+    xobjects.sort_by_key(|(name, _)| name.to_vec());
+    xobjects.into_iter().map(|(_, id)| Ok(id.as_reference()?)).collect()
+}
+
+/// Looks up the object ID of an XObject in the resources of the page tree root.
+pub(crate) fn xobject_id(doc: &Document, name: &str) -> Result<ObjectId, Error> {
+    let pages = doc.catalog()?.get(b"Pages")?.as_reference()?;
+    let resources = doc.get_dictionary(pages)?.get(b"Resources")?.as_reference()?;
+    let xobjects = doc.get_dictionary(resources)?.get(b"XObject")?.as_dict()?;
+    Ok(xobjects.get(name.as_bytes())?.as_reference()?)
+}
+
+/// Returns the IDs of the text fields of the interactive form in order, which hold the padding
+/// unlike a signature field.
+pub(crate) fn form_field_ids(doc: &Document) -> Result<Vec<ObjectId>, Error> {
+    let Ok(form) = doc.catalog()?.get(b"AcroForm") else {
+        return Ok(Vec::new());
+    };
+    let mut field_ids = Vec::new();
+    for field in form.as_dict()?.get(b"Fields")?.as_array()? {
+        let field_id = field.as_reference()?;
+        if doc.get_dictionary(field_id)?.get(b"FT")?.as_name()? == b"Tx" {
+            field_ids.push(field_id);
+        }
+    }
+    Ok(field_ids)
+}
+
+/// Returns the IDs of the annotations on all pages in order, except for those of form fields.
+pub(crate) fn annotation_ids(doc: &Document) -> Result<Vec<ObjectId>, Error> {
+    let mut annotation_ids = Vec::new();
+    for page_id in doc.get_pages().into_values() {
+        let Ok(annotations) = doc.get_dictionary(page_id)?.get(b"Annots") else {
+            continue;
+        };
+        for annotation in annotations.as_array()? {
+            let annotation_id = annotation.as_reference()?;
+            if doc.get_dictionary(annotation_id)?.get(b"Subtype")?.as_name()? != b"Widget" {
+                annotation_ids.push(annotation_id);
+            }
+        }
+    }
+    Ok(annotation_ids)
+}
+
+/// Returns the IDs of the content streams of the first page in order.
+pub(crate) fn first_page_contents(doc: &Document) -> Result<Vec<ObjectId>, Error> {
+    references_of(doc.get_dictionary(first_page(doc)?)?.get(b"Contents")?)
+}
+
+/// Returns the IDs of the content streams of all pages in order. Only the first page holds the
+/// padding, the others hold the hidden text of [`Options::page_content_sizes`], if any.
+pub(crate) fn page_contents(doc: &Document) -> Result<Vec<ObjectId>, Error> {
+    let mut content_ids = Vec::new();
+    for page_id in doc.get_pages().into_values() {
+        if let Ok(contents) = doc.get_dictionary(page_id)?.get(b"Contents") {
+            content_ids.extend(references_of(contents)?);
+        }
+    }
+    Ok(content_ids)
+}
+
+/// Returns the IDs `/Contents` refers to, which is either a single reference or an array of them.
+fn references_of(contents: &Object) -> Result<Vec<ObjectId>, Error> {
+    match contents {
+        Object::Array(contents) => contents.iter().map(|id| Ok(id.as_reference()?)).collect(),
+        content => Ok(vec![content.as_reference()?]),
+    }
+}
+
+/// Serializes `doc` into memory. Saving mutates the trailer, so each document should only be saved once.
+#[cfg_attr(feature = "tracing", tracing::instrument(name = "save", level = "debug", skip_all, fields(len)))]
+pub(crate) fn save_to_vec(doc: &mut Document) -> Result<Vec<u8>, Error> {
+    let mut bytes = Vec::new();
+    doc.save_to(&mut bytes)?;
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("len", bytes.len());
+    Ok(bytes)
+}
+
+fn fill(bytes: &mut [u8], options: &Options) {
+    bytes.fill(filler_byte(options))
+}
+
+fn filler_byte(options: &Options) -> u8 {
+    if options.zero_filler {
+        0
+    } else {
+        b'4'
+    }
+}
+
+/// Checks that lopdf can address every byte of a document of `file_size_bytes` bytes. It writes the
+/// offsets of the cross reference section with 32 bits, larger documents would get a corrupt one.
+pub(crate) fn validate_lopdf_size(file_size_bytes: usize) -> Result<(), Error> {
+    if file_size_bytes > u32::MAX as usize {
+        return Err(Error::InvalidOption(format!(
+            "lopdf writes offsets of 32 bits, which a document of {file_size_bytes} bytes exceeds. Larger documents \
+            require the native writer."
+        )));
+    }
+    Ok(())
+}
+
+/// Returns the size of the document built from `layout` for a file of `file_size_bytes` bytes.
+fn measure(
+    options: &Options,
+    id: Option<&[u8]>,
+    file_size_bytes: usize,
+    layout: Layout,
+    scratch: &mut Scratch,
+) -> Result<usize, Error> {
+    scratch.measure(|scratch| {
+        let mut doc = build_document(options, id, layout, scratch)?;
+        signature::set_byte_range(&mut doc, file_size_bytes)?;
+        let len = serialized_len(&mut doc)?;
+        scratch.reclaim(doc);
+        Ok(len)
+    })
+}
+
+/// Returns the amount of bytes `doc` occupies once saved. Saving mutates the trailer, so the document
+/// should be discarded afterwards.
+#[cfg_attr(feature = "tracing", tracing::instrument(name = "measure", level = "trace", skip_all, ret))]
+fn serialized_len(doc: &mut Document) -> Result<usize, Error> {
+    let mut sink = CountingSink::default();
+    doc.save_to(&mut sink)?;
+    Ok(sink.bytes_written)
 }
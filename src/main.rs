@@ -1,10 +1,373 @@
-use generatePDF::generate_pdf_with_size;
-
-fn main() {
-    let args = std::env::args().collect::<Vec<_>>();
-    let file_name = &args[1];
-    let file_size_bytes: usize = args[2].parse().unwrap();
-    generate_pdf_with_size(file_size_bytes).unwrap()
-        .save(file_name)
-        .unwrap();
+#![allow(non_snake_case)]
+
+use std::error::Error;
+use std::fmt::Display;
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use generatePDF::progress::ProgressWriter;
+use generatePDF::sparse::{save_sparse_pdf, SparseWriter};
+use generatePDF::verify::{verify_pdf, SizeTarget};
+use generatePDF::{generate_pdf_with_options, minimum_size, plan_with_options, Options};
+use lopdf::Document;
+
+/// Width of the progress bar in characters.
+const PROGRESS_BAR_WIDTH: usize = 40;
+
+/// Prints the error of a failed command and exits with status 1.
+fn main() -> ExitCode {
+    match run() {
+        Ok(code) => code,
+        Err(error) => {
+            eprintln!("{error}");
+            ExitCode::FAILURE
+        }
+    }
+}
+
+fn run() -> Result<ExitCode, Box<dyn Error>> {
+    #[cfg(feature = "batch")]
+    if std::env::args().nth(1).as_deref() == Some("batch") {
+        let manifest = std::env::args().nth(2).ok_or("batch requires the path of a manifest")?;
+        generatePDF::batch::run(manifest)?;
+        return Ok(ExitCode::SUCCESS);
+    }
+    if std::env::args().nth(1).as_deref() == Some("verify") {
+        return verify(std::env::args().skip(2));
+    }
+
+    let mut args = std::env::args().skip(1);
+    let mut positional = Vec::new();
+    let mut options = Options::default();
+    let mut progress = false;
+    let mut sparse = false;
+    let mut sizes = None;
+    let mut total = None;
+    let mut random_shares = false;
+    let mut count = 1;
+    let mut seed = None;
+    let mut block_size = 1;
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--page-size" => options = options.page_size(value(&mut args, &arg)?.parse()?),
+            "--pdf-version" => options = options.pdf_version(value(&mut args, &arg)?.parse()?),
+            "--trailer-id" => options = options.trailer_id(value(&mut args, &arg)?.parse()?),
+            "--size" => sizes = Some(parse_size_range(&value(&mut args, &arg)?)?),
+            "--total" => total = Some(parse_size(&value(&mut args, &arg)?)?),
+            "--shares" => match value(&mut args, &arg)?.as_str() {
+                "equal" => random_shares = false,
+                "random" => random_shares = true,
+                _ => return Err("--shares requires either equal or random".into()),
+            },
+            "--count" => count = number(&value(&mut args, &arg)?, &arg)?,
+            "--seed" => seed = Some(number(&value(&mut args, &arg)?, &arg)?),
+            "--align" => {
+                block_size = parse_size(&value(&mut args, &arg)?)?.max(1);
+                options = options.block_size(block_size);
+            }
+            "--decompressed-size" => options = options.decompressed_size(parse_size(&value(&mut args, &arg)?)?),
+            "--filters" => {
+                let filters = value(&mut args, &arg)?;
+                let filters = filters.split(',').map(str::parse).collect::<Result<Vec<_>, _>>()?;
+                options = options.stream_filters(filters);
+            }
+            "--progress" => progress = true,
+            "--sparse" => {
+                sparse = true;
+                options = options.zero_filler(true);
+            }
+            _ => positional.push(arg),
+        }
+    }
+
+    if positional.first().map(String::as_str) == Some("inspect") {
+        let (size, max) = match sizes {
+            Some(sizes) => sizes,
+            None => {
+                let size = parse_size(positional.get(1).ok_or("inspect requires a size")?)?;
+                (size, size)
+            }
+        };
+        if size != max {
+            return Err("inspect requires a single size".into());
+        }
+        println!("{}", plan_with_options(align(size, &options), &options)?);
+        return Ok(ExitCode::SUCCESS);
+    }
+
+    let file_name = positional.first().ok_or("expected the path of the file to generate")?;
+    let mut random = SplitMix64::new(seed.unwrap_or_else(|| {
+        SystemTime::now().duration_since(UNIX_EPOCH).unwrap_or_default().as_nanos() as u64
+    }));
+    let (file_sizes, sampled) = match total {
+        Some(total) => {
+            if sizes.is_some() {
+                return Err("--total and --size are mutually exclusive".into());
+            }
+            let minimum = minimum_size(&options)?;
+            (split_budget(total, count, minimum, block_size, random_shares.then_some(&mut random))?, random_shares)
+        }
+        None => {
+            let (min, max) = match sizes {
+                Some(sizes) => sizes,
+                None => {
+                    let size = parse_size(positional.get(1).ok_or("expected the size of the file, --size or --total")?)?;
+                    (size, size)
+                }
+            };
+            // only whole blocks are sampled, a single size is rounded up to one
+            let (min_blocks, max_blocks) = if min == max {
+                let blocks = align(min, &options) / block_size;
+                (blocks, blocks)
+            } else {
+                (min.div_ceil(block_size), max / block_size)
+            };
+            if min_blocks > max_blocks {
+                return Err(format!("no multiple of {block_size} bytes between {min} and {max} bytes").into());
+            }
+            let blocks = (0..count)
+                .map(|_| min_blocks + random.below(((max_blocks - min_blocks) as u64).wrapping_add(1)) as usize);
+            (blocks.map(|blocks| blocks * block_size).collect(), min != max)
+        }
+    };
+    if sampled && seed.is_none() {
+        eprintln!("Sampled sizes with --seed {}", random.seed);
+    }
+
+    for (index, file_size_bytes) in file_sizes.into_iter().enumerate() {
+        let file_name = if count == 1 { file_name.clone() } else { numbered(file_name, index, count) };
+        if sparse && !progress {
+            save_sparse_pdf(file_size_bytes, &options, &file_name)?;
+            continue;
+        }
+        // the document is generated before the file is created, so a document which cannot be
+        // generated does not leave an empty file behind
+        let mut progress = progress.then(progress_bar);
+        if let Some(progress) = &mut progress {
+            progress(0, file_size_bytes);
+        }
+        let mut doc = generate_pdf_with_options(file_size_bytes, &options).inspect_err(|_| {
+            if progress.is_some() {
+                // ends the line of the progress bar before the error
+                eprintln!();
+            }
+        })?;
+        if sparse {
+            let mut file = SparseWriter::create(&file_name)?;
+            save(&mut doc, &mut file, file_size_bytes, progress)?;
+            file.finish()?;
+        } else {
+            save(&mut doc, BufWriter::new(File::create(&file_name)?), file_size_bytes, progress)?;
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// Verifies the file given as the first argument, which is expected to have the size or range of
+/// sizes given as the second one, if any. Fails with status 1 if a problem was found.
+fn verify(mut args: impl Iterator<Item = String>) -> Result<ExitCode, Box<dyn Error>> {
+    let file_name = args.next().ok_or("verify requires the path of a file")?;
+    let expected = match args.next().map(|size| parse_size_range(&size)).transpose()? {
+        Some((min, max)) if min == max => SizeTarget::Exact(min),
+        Some((min, max)) => SizeTarget::Range { min, max },
+        None => SizeTarget::Any,
+    };
+    let report = verify_pdf(&file_name, expected).map_err(|error| format!("{file_name}: {error}"))?;
+    println!("{file_name}: {report}");
+    Ok(if report.is_valid() { ExitCode::SUCCESS } else { ExitCode::FAILURE })
+}
+
+/// Returns the value following the option `name`.
+fn value(args: &mut impl Iterator<Item = String>, name: &str) -> Result<String, String> {
+    args.next().ok_or_else(|| format!("{name} requires a value"))
+}
+
+/// Parses the number given as the value of the option `name`.
+fn number<T: FromStr>(value: &str, name: &str) -> Result<T, String>
+where
+    T::Err: Display,
+{
+    value.parse().map_err(|error| format!("Invalid value {value} of {name}: {error}"))
+}
+
+/// Rounds `file_size_bytes` up to a multiple of the block size of `options`, telling the user if
+/// that changed it.
+fn align(file_size_bytes: usize, options: &Options) -> usize {
+    let aligned = options.align(file_size_bytes);
+    if aligned != file_size_bytes {
+        eprintln!("Rounded {file_size_bytes} bytes up to {aligned} bytes, a multiple of the block size");
+    }
+    aligned
+}
+
+/// Splits `total` bytes into `count` file sizes of at least `minimum` bytes each, either equally or
+/// at random. All sizes are multiples of `block_size`, which `total` and `minimum` have to be as
+/// well.
+fn split_budget(
+    total: usize,
+    count: usize,
+    minimum: usize,
+    block_size: usize,
+    random: Option<&mut SplitMix64>,
+) -> Result<Vec<usize>, String> {
+    if count == 0 {
+        return Err("--count must be at least 1".into());
+    }
+    if !total.is_multiple_of(block_size) {
+        return Err(format!("--total of {total} bytes is not a multiple of the block size of {block_size} bytes"));
+    }
+    let spare = minimum
+        .checked_mul(count)
+        .and_then(|required| total.checked_sub(required))
+        .ok_or_else(|| format!("{total} bytes are not enough for {count} files of at least {minimum} bytes"))?
+        / block_size;
+
+    let shares: Vec<usize> = match random {
+        // cutting the spare bytes at random points yields uniformly distributed shares of them
+        Some(random) => {
+            let mut cuts: Vec<usize> = (1..count).map(|_| random.below(spare as u64 + 1) as usize).collect();
+            cuts.sort_unstable();
+            cuts.push(spare);
+            cuts.iter().scan(0, |start, &cut| Some(cut - std::mem::replace(start, cut))).collect()
+        }
+        None => (0..count).map(|index| spare / count + usize::from(index < spare % count)).collect(),
+    };
+    Ok(shares.into_iter().map(|share| minimum + share * block_size).collect())
+}
+
+/// Parses a size like `1500`, `64KiB` or `10MB`.
+fn parse_size(size: &str) -> Result<usize, String> {
+    let digits = size.find(|char: char| !char.is_ascii_digit()).unwrap_or(size.len());
+    let (number, unit) = size.split_at(digits);
+    let factor = match unit.trim().to_ascii_lowercase().as_str() {
+        "" | "b" => 1,
+        "kb" => 1000,
+        "kib" => 1 << 10,
+        "mb" => 1000 * 1000,
+        "mib" => 1 << 20,
+        "gb" => 1000 * 1000 * 1000,
+        "gib" => 1 << 30,
+        _ => return Err(format!("Unknown unit {unit} in size {size}, expected B, kB, KiB, MB, MiB, GB or GiB.")),
+    };
+    let number: usize = number.parse().map_err(|error| format!("Invalid size {size}: {error}"))?;
+    number.checked_mul(factor).ok_or_else(|| format!("The size {size} is out of range."))
+}
+
+/// Parses either a single size or a range of sizes like `1MiB..10MiB`, which excludes its end
+/// unless written as `1MiB..=10MiB`. Returns the smallest and largest size.
+fn parse_size_range(range: &str) -> Result<(usize, usize), String> {
+    let Some((start, end)) = range.split_once("..") else {
+        let size = parse_size(range)?;
+        return Ok((size, size));
+    };
+    let (start, end) = match end.strip_prefix('=') {
+        Some(end) => (parse_size(start)?, Some(parse_size(end)?)),
+        None => (parse_size(start)?, parse_size(end)?.checked_sub(1)),
+    };
+    match end {
+        Some(end) if start <= end => Ok((start, end)),
+        _ => Err(format!("empty size range {range}")),
+    }
+}
+
+/// Inserts `index` in front of the extension of `file_name`, padded to the width of the largest one.
+fn numbered(file_name: &str, index: usize, count: usize) -> String {
+    let path = Path::new(file_name);
+    let width = (count - 1).to_string().len();
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let name = match path.extension() {
+        Some(extension) => format!("{stem}-{index:0width$}.{}", extension.to_string_lossy()),
+        None => format!("{stem}-{index:0width$}"),
+    };
+    path.with_file_name(name).to_string_lossy().into_owned()
+}
+
+/// The SplitMix64 generator, which is small and produces the same sequence on every platform, so a
+/// seed always picks the same sizes.
+struct SplitMix64 {
+    seed: u64,
+    state: u64,
+}
+
+impl SplitMix64 {
+    fn new(seed: u64) -> Self {
+        SplitMix64 { seed, state: seed }
+    }
+
+    fn next(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a uniformly distributed number below `bound`, rejecting the values which would
+    /// favour small numbers.
+    fn below(&mut self, bound: u64) -> u64 {
+        if bound == 0 {
+            // the range spans all 64 bits
+            return self.next();
+        }
+        let zone = u64::MAX - u64::MAX % bound;
+        loop {
+            let value = self.next();
+            if value < zone {
+                return value % bound;
+            }
+        }
+    }
+}
+
+/// Saves `doc` to `target`, drawing the progress to stderr if `progress` is given.
+fn save(
+    doc: &mut Document,
+    mut target: impl Write,
+    file_size_bytes: usize,
+    progress: Option<impl FnMut(usize, usize)>,
+) -> std::io::Result<()> {
+    match progress {
+        Some(progress) => {
+            let mut target = ProgressWriter::new(target, file_size_bytes, progress);
+            doc.save_to(&mut target)?;
+            target.flush()?;
+            eprintln!();
+        }
+        None => {
+            doc.save_to(&mut target)?;
+            target.flush()?;
+        }
+    }
+    Ok(())
+}
+
+/// Returns a progress callback drawing a bar to stderr, redrawn whenever another percent is done.
+/// Until the first byte is written, the document is being generated.
+fn progress_bar() -> impl FnMut(usize, usize) {
+    let mut drawn = None;
+    move |written, total| {
+        if written == 0 {
+            eprint!("\rpreparing {} MiB", total >> 20);
+            drawn = None;
+            return;
+        }
+        let percent = written * 100 / total.max(1);
+        if drawn == Some(percent) {
+            return;
+        }
+        drawn = Some(percent);
+
+        let filled = PROGRESS_BAR_WIDTH * percent / 100;
+        eprint!(
+            "\r[{}{}] {percent:>3}% {} / {} MiB",
+            "#".repeat(filled),
+            " ".repeat(PROGRESS_BAR_WIDTH - filled),
+            written >> 20,
+            total >> 20,
+        );
+    }
 }
@@ -0,0 +1,402 @@
+//! A serializer writing documents directly instead of through lopdf, available with the
+//! `native-writer` feature, which is enabled by default.
+//!
+//! Every byte of the document is written here, so its size is a formula of the amount of filler
+//! rather than something to measure, and the filler is streamed to the target in chunks. Documents
+//! are written in constant memory and the output does not depend on how lopdf formats objects. The
+//! content stream holding the filler is the last object, so the ten digit offsets of the cross
+//! reference table stay small and documents of any size can be written.
+//!
+//! Only the options which change the skeleton in a fixed way are supported: the page size, the PDF
+//! version, NUL filler, the [block size](Options::block_size), the
+//! [trailer ID](Options::trailer_id) and the [line endings](Options::line_ending),
+//! [generation numbers](Options::object_generation) and [free objects](Options::free_objects),
+//! which lopdf does not offer any control over. The output is always
+//! [deterministic](Options::deterministic). Any other option is rejected with
+//! [`Error::InvalidOption`]. A cross reference table is written for every version, which PDF 2.0
+//! still permits.
+
+use std::io::Write;
+
+use sha2::{Digest, Sha256};
+
+use crate::{
+    filler_byte, trailer_id, Error, GenerationPlan, LineEnding, Options, TrailerId, HIDDEN_TEXT_END, HIDDEN_TEXT_START,
+};
+
+/// Amount of filler bytes written at once.
+const CHUNK_SIZE: usize = 1 << 16;
+
+/// Every entry of the cross reference table takes 20 bytes including its two byte ending.
+const XREF_ENTRY_LEN: usize = 20;
+/// The content stream holding the filler is object 6, see [`Skeleton::new`].
+const OBJECT_COUNT: usize = 6;
+
+/// Writes a document of exactly `file_size_bytes` bytes to `target`. The filler is placed like
+/// [`PaddingStrategy::Text`](crate::PaddingStrategy::Text) does.
+pub fn write_pdf(file_size_bytes: usize, options: &Options, target: impl Write) -> Result<(), Error> {
+    let byte = filler_byte(options);
+    Solved::new(file_size_bytes, options)?.write_to(target, |target, len| {
+        let chunk = vec![byte; CHUNK_SIZE.min(len)];
+        for start in (0..len).step_by(CHUNK_SIZE) {
+            target.write_all(&chunk[..CHUNK_SIZE.min(len - start)])?;
+        }
+        Ok(())
+    })
+}
+
+/// A document whose layout has been computed, so everything but writing it has succeeded.
+pub(crate) struct Solved {
+    skeleton: Skeleton,
+    file_size_bytes: usize,
+    filler: usize,
+    slack: usize,
+}
+
+impl Solved {
+    #[cfg_attr(
+        feature = "tracing",
+        tracing::instrument(name = "native", level = "debug", skip_all, fields(file_size_bytes = file_size_bytes))
+    )]
+    pub(crate) fn new(file_size_bytes: usize, options: &Options) -> Result<Self, Error> {
+        let skeleton = Skeleton::new(file_size_bytes, options)?;
+        let (filler, slack) = skeleton.layout(file_size_bytes)?;
+        #[cfg(feature = "tracing")]
+        tracing::debug!(filler, slack, "computed the layout");
+        Ok(Solved { skeleton, file_size_bytes, filler, slack })
+    }
+
+    /// Writes the document to `target`, leaving the filler to `write_filler`, which gets the amount of
+    /// bytes of it to write.
+    pub(crate) fn write_to<W: Write>(
+        &self,
+        mut target: W,
+        write_filler: impl FnOnce(&mut W, usize) -> std::io::Result<()>,
+    ) -> Result<(), Error> {
+        let skeleton = &self.skeleton;
+        let mut target = OffsetWriter { inner: &mut target, offset: 0 };
+        let mut offsets = Vec::with_capacity(OBJECT_COUNT);
+        target.write_all(&skeleton.header)?;
+        for object in &skeleton.objects {
+            offsets.push(target.offset);
+            target.write_all(object)?;
+        }
+
+        // the content stream comes last, so the filler only moves the cross reference table
+        offsets.push(target.offset);
+        target.write_all(&skeleton.stream_start(self.filler))?;
+        target.write_all(&skeleton.text_start)?;
+        write_filler(target.inner, self.filler)?;
+        target.offset += self.filler;
+        target.write_all(&skeleton.text_end)?;
+        target.write_all(&skeleton.stream_end)?;
+
+        let xref_start = target.offset;
+        target.write_all(&skeleton.xref_start)?;
+        target.write_all(&skeleton.free_head)?;
+        for offset in offsets {
+            write!(target, "{offset:010} {:05} n", skeleton.generation)?;
+            target.write_all(skeleton.entry_ending)?;
+        }
+        target.write_all(&skeleton.free_entries)?;
+        target.write_all(&skeleton.trailer)?;
+        target.write_all(&vec![b' '; self.slack])?;
+        target.write_all(&skeleton.end(xref_start))?;
+        target.flush()?;
+
+        if target.offset != self.file_size_bytes {
+            return Err(Error::IOError(std::io::Error::other(format!(
+                "Wrote {} bytes instead of the requested {} bytes.",
+                target.offset, self.file_size_bytes
+            ))));
+        }
+        Ok(())
+    }
+}
+
+impl Solved {
+    /// Breaks the document down like [`plan_with_options`](crate::plan_with_options).
+    pub(crate) fn plan(&self, options: &Options) -> GenerationPlan {
+        let skeleton = &self.skeleton;
+        let padding = skeleton.text_len(self.filler);
+        let xref_start = skeleton.xref_offset(self.filler);
+        let minimum_overhead = skeleton.len(0) - skeleton.text_len(0);
+        GenerationPlan {
+            file_size: self.file_size_bytes,
+            minimum_size: options.align(skeleton.len(0)),
+            structure: xref_start - padding,
+            padding,
+            xref: self.file_size_bytes - xref_start - self.slack,
+            slack: self.slack,
+            digit_adjustment: self.file_size_bytes - padding - self.slack - minimum_overhead,
+        }
+    }
+}
+
+/// The parts of the document which do not depend on the amount of filler.
+struct Skeleton {
+    header: Vec<u8>,
+    /// Objects 1 to 5, which precede the content stream.
+    objects: Vec<Vec<u8>>,
+    /// The trailer dictionary, which is followed by the slack.
+    trailer: Vec<u8>,
+    /// The operators around the filler in the content stream.
+    text_start: Vec<u8>,
+    text_end: Vec<u8>,
+    /// The keywords closing the content stream object.
+    stream_end: Vec<u8>,
+    /// The keyword and the subsection heading the cross reference table.
+    xref_start: Vec<u8>,
+    /// The entry of object 0 heading the free list, which precedes the entries of the objects.
+    free_head: Vec<u8>,
+    /// The entries of the free objects, which follow those of the objects.
+    free_entries: Vec<u8>,
+    entry_ending: &'static [u8],
+    generation: u16,
+    line_ending: LineEnding,
+}
+
+/// Whether the native writer supports `options`, which may only differ from the defaults in the
+/// options listed in the [module documentation](self).
+pub(crate) fn supports(options: &Options) -> bool {
+    let supported = Options {
+        page_size: options.page_size,
+        pdf_version: options.pdf_version,
+        zero_filler: options.zero_filler,
+        deterministic: options.deterministic,
+        block_size: options.block_size,
+        trailer_id: options.trailer_id.clone(),
+        line_ending: options.line_ending,
+        xref_entry_ending: options.xref_entry_ending,
+        object_generation: options.object_generation,
+        free_objects: options.free_objects,
+        ..Options::default()
+    };
+    *options == supported
+}
+
+impl Skeleton {
+    fn new(file_size_bytes: usize, options: &Options) -> Result<Self, Error> {
+        if !supports(options) {
+            return Err(Error::InvalidOption(
+                "The native writer only supports page size, PDF version, NUL filler, block size, trailer ID, \
+                 line endings, generation numbers and free objects."
+                    .into(),
+            ));
+        }
+        options.validate()?;
+        options.validate_size(file_size_bytes)?;
+
+        // the comment of high bytes marks the file as binary for transfer programs
+        let line_ending = options.line_ending;
+        let mut header = format!("%PDF-{}\n", options.pdf_version.as_str()).into_bytes();
+        header.extend(b"%\xE2\xE3\xCF\xD3\n");
+        let header = line_ending.apply(&header);
+
+        let (width, height) = options.page_size.dimensions();
+        let generation = options.object_generation;
+        let objects = [
+            format!(
+                "1 {generation} obj\n<</Type/Pages/Kids[4 {generation} R]/Count 1/Resources 3 {generation} R\
+                 /MediaBox[0 0 {width} {height}]>>\nendobj\n"
+            ),
+            format!("2 {generation} obj\n<</Type/Font/Subtype/Type1/BaseFont/Courier>>\nendobj\n"),
+            format!("3 {generation} obj\n<</Font<</F1 2 {generation} R>>>>\nendobj\n"),
+            format!("4 {generation} obj\n<</Type/Page/Parent 1 {generation} R/Contents 6 {generation} R>>\nendobj\n"),
+            format!("5 {generation} obj\n<</Type/Catalog/Pages 1 {generation} R>>\nendobj\n"),
+        ];
+        let objects: Vec<Vec<u8>> = objects.iter().map(|object| line_ending.apply(object.as_bytes())).collect();
+
+        // the automatic file identifier is derived from the size and the skeleton, like that of
+        // deterministic documents written by lopdf
+        let id = match options.trailer_id {
+            TrailerId::Automatic => {
+                let mut hasher =
+                    Sha256::new().chain_update((file_size_bytes as u64).to_be_bytes()).chain_update(&header);
+                for object in &objects {
+                    hasher.update(object);
+                }
+                Some(hasher.finalize()[..16].to_vec())
+            }
+            _ => trailer_id(file_size_bytes, options)?,
+        };
+        let id = match id {
+            Some(id) => {
+                let id: String = id.iter().map(|byte| format!("{byte:02X}")).collect();
+                format!("/ID[<{id}><{id}>]")
+            }
+            None => String::new(),
+        };
+        let size = OBJECT_COUNT + 1 + options.free_objects;
+        let trailer = format!("trailer\n<</Size {size}/Root 5 {generation} R{id}>>");
+
+        // the free objects follow the objects, every entry links to the next free object and the
+        // last one back to object 0. Their generation would be used if their number was reused.
+        let entry_ending = options.xref_entry_ending.as_bytes();
+        let free_entry = |next: usize, generation: u16| {
+            [format!("{next:010} {generation:05} f").as_bytes(), entry_ending].concat()
+        };
+        let first_free = if options.free_objects > 0 { OBJECT_COUNT + 1 } else { 0 };
+        let free_head = free_entry(first_free, u16::MAX);
+        let free_entries = (OBJECT_COUNT + 1..size)
+            .flat_map(|number| free_entry((number + 1) % size, generation.saturating_add(1)))
+            .collect();
+
+        Ok(Skeleton {
+            header,
+            objects,
+            trailer: line_ending.apply(trailer.as_bytes()),
+            text_start: line_ending.apply(HIDDEN_TEXT_START),
+            text_end: line_ending.apply(HIDDEN_TEXT_END),
+            stream_end: line_ending.apply(b"\nendstream\nendobj\n"),
+            xref_start: line_ending.apply(format!("xref\n0 {size}\n").as_bytes()),
+            free_head,
+            free_entries,
+            entry_ending,
+            generation,
+            line_ending,
+        })
+    }
+
+    /// Returns the size of the document with `filler` bytes of filler and no slack.
+    fn len(&self, filler: usize) -> usize {
+        let xref_start = self.xref_offset(filler);
+        let entries = self.free_head.len() + OBJECT_COUNT * XREF_ENTRY_LEN + self.free_entries.len();
+        let xref = self.xref_start.len() + entries;
+        xref_start + xref + self.trailer.len() + self.end(xref_start).len()
+    }
+
+    /// The offset of the cross reference table, which directly follows the last object.
+    fn xref_offset(&self, filler: usize) -> usize {
+        let objects = self.objects.iter().map(Vec::len).sum::<usize>();
+        let stream = self.stream_start(filler).len() + self.text_len(filler) + self.stream_end.len();
+        self.header.len() + objects + stream
+    }
+
+    /// The data of the content stream with `filler` bytes of filler.
+    fn text_len(&self, filler: usize) -> usize {
+        self.text_start.len() + filler + self.text_end.len()
+    }
+
+    /// The start of the content stream object up to its data.
+    fn stream_start(&self, filler: usize) -> Vec<u8> {
+        let length = self.text_len(filler);
+        self.line_ending.apply(format!("6 {} obj\n<</Length {length}>>\nstream\n", self.generation).as_bytes())
+    }
+
+    /// The offset of the cross reference table and the end of file marker.
+    fn end(&self, xref_start: usize) -> Vec<u8> {
+        self.line_ending.apply(format!("\nstartxref\n{xref_start}\n%%EOF\n").as_bytes())
+    }
+
+    /// Finds the amount of filler and slack which add up to `file_size_bytes`. Another byte of
+    /// filler may add a digit to the `/Length` and the offset of the cross reference table at once,
+    /// the sizes skipped by that are reached with slack after the trailer dictionary.
+    fn layout(&self, file_size_bytes: usize) -> Result<(usize, usize), Error> {
+        let minimum = self.len(0);
+        if file_size_bytes < minimum {
+            return Err(Error::FileTooSmall { requested: file_size_bytes, minimum });
+        }
+        let mut filler = file_size_bytes - minimum;
+        while self.len(filler) > file_size_bytes {
+            filler -= 1;
+        }
+        Ok((filler, file_size_bytes - self.len(filler)))
+    }
+}
+
+/// A writer keeping track of the offset of the next byte, which the cross reference table lists
+/// for every object.
+struct OffsetWriter<W> {
+    inner: W,
+    offset: usize,
+}
+
+impl<W: Write> Write for OffsetWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.offset += written;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{write_pdf, Solved};
+    use crate::sizing::CountingSink;
+    use crate::verify::{verify_pdf_bytes, SizeTarget};
+    use crate::{Error, LineEnding, Options, PdfVersion, TrailerId, XrefEntryEnding};
+
+    fn minimum(options: &Options) -> usize {
+        match write_pdf(0, options, std::io::sink()) {
+            Err(Error::FileTooSmall { minimum, .. }) => minimum,
+            result => panic!("an empty document was written: {result:?}"),
+        }
+    }
+
+    #[test]
+    fn every_size_from_the_minimum() {
+        let option_sets = [
+            Options::default(),
+            Options::default().zero_filler(true).pdf_version(PdfVersion::V1_4).trailer_id(TrailerId::Omitted),
+            Options::default()
+                .line_ending(LineEnding::CarriageReturnLineFeed)
+                .xref_entry_ending(XrefEntryEnding::CarriageReturnLineFeed),
+            Options::default().object_generation(7).free_objects(3).pdf_version(PdfVersion::V2_0),
+        ];
+        for options in option_sets {
+            let minimum = minimum(&options);
+            // the offsets and the length of the content stream gain digits at the powers of ten
+            for size in (minimum..minimum + 64).chain(1_000 - 32..1_000 + 32).chain(10_000 - 32..10_000 + 32) {
+                let mut bytes = Vec::new();
+                write_pdf(size, &options, &mut bytes).unwrap_or_else(|error| panic!("{size} bytes: {error}"));
+                assert_eq!(bytes.len(), size);
+                assert!(verify_pdf_bytes(&bytes, SizeTarget::Exact(size)).is_valid(), "{size} bytes");
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn sizes_beyond_ten_digit_offsets() {
+        // the offsets of the objects stay small, only the one after `startxref` gains digits
+        for size in (10_000_000_000 - 16..10_000_000_000 + 16).chain([50_000_000_000]) {
+            let mut sink = CountingSink::default();
+            write_pdf(size, &Options::default(), &mut sink).unwrap_or_else(|error| panic!("{size} bytes: {error}"));
+            assert_eq!(sink.bytes_written, size);
+        }
+    }
+
+    #[test]
+    fn plans_the_written_document() {
+        let options = Options::default();
+        let minimum = minimum(&options);
+        for size in (minimum..minimum + 64).chain(10_000 - 32..10_000 + 32) {
+            let mut bytes = Vec::new();
+            write_pdf(size, &options, &mut bytes).unwrap();
+            let plan = Solved::new(size, &options).unwrap().plan(&options);
+            assert_eq!(plan.structure + plan.padding + plan.xref + plan.slack, size);
+            assert_eq!(plan.minimum_size, minimum);
+
+            let text = String::from_utf8_lossy(&bytes);
+            let startxref = text.rsplit("startxref\n").next().unwrap().lines().next().unwrap();
+            assert_eq!(startxref.parse::<usize>().unwrap(), plan.structure + plan.padding, "{size} bytes");
+            assert!(text.contains(&format!("<</Length {}>>", plan.padding)), "{size} bytes");
+        }
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn plans_documents_beyond_the_offsets_of_lopdf() {
+        for size in [5 << 30, 10_000_000_000] {
+            let plan = crate::plan_with_options(size, &Options::default()).unwrap();
+            assert_eq!(plan.file_size, size);
+            assert_eq!(plan.structure + plan.padding + plan.xref + plan.slack, size);
+            assert_eq!(plan.minimum_size, minimum(&Options::default()));
+        }
+    }
+}
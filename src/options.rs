@@ -0,0 +1,663 @@
+use std::str::FromStr;
+
+use lopdf::{Dictionary, Object, StringFormat};
+
+use crate::compressed::MIN_DECOMPRESSED_SIZE;
+use crate::{CjkFont, Error, PaddingStrategy, StreamFilter};
+
+/// Settings for the generated document. The size guarantee holds for every combination of options,
+/// the padding shrinks to make room for the requested content.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub(crate) info: Info,
+    pub(crate) xmp_metadata: bool,
+    pub(crate) padding: PaddingStrategy,
+    pub(crate) page_size: PageSize,
+    pub(crate) caption: Option<String>,
+    pub(crate) unicode_caption: Option<CjkFont>,
+    pub(crate) padding_checksum: bool,
+    pub(crate) deterministic: bool,
+    pub(crate) zero_filler: bool,
+    pub(crate) content_stream_limit: Option<usize>,
+    pub(crate) blank_pages: usize,
+    pub(crate) page_content_sizes: Vec<usize>,
+    pub(crate) form_fields: usize,
+    pub(crate) annotations_per_page: usize,
+    pub(crate) bookmarks: usize,
+    pub(crate) bookmark_depth: usize,
+    pub(crate) benign_javascript: bool,
+    pub(crate) signature_placeholder: Option<usize>,
+    pub(crate) pdf_version: PdfVersion,
+    pub(crate) block_size: Option<usize>,
+    pub(crate) decompressed_size: Option<usize>,
+    pub(crate) stream_filters: Vec<StreamFilter>,
+    pub(crate) trailer_id: TrailerId,
+    pub(crate) line_ending: LineEnding,
+    pub(crate) xref_entry_ending: XrefEntryEnding,
+    pub(crate) object_generation: u16,
+    pub(crate) free_objects: usize,
+    /// Length of the string reserved in the catalog for the nested objects of [`crate::stress`].
+    pub(crate) nesting_placeholder: Option<usize>,
+}
+
+impl Options {
+    /// Sets the `/Title` entry of the document information dictionary.
+    pub fn title(mut self, title: impl Into<String>) -> Self {
+        self.info.title = Some(title.into());
+        self
+    }
+
+    /// Sets the `/Author` entry of the document information dictionary.
+    pub fn author(mut self, author: impl Into<String>) -> Self {
+        self.info.author = Some(author.into());
+        self
+    }
+
+    /// Sets the `/Subject` entry of the document information dictionary.
+    pub fn subject(mut self, subject: impl Into<String>) -> Self {
+        self.info.subject = Some(subject.into());
+        self
+    }
+
+    /// Sets the `/Producer` entry of the document information dictionary.
+    pub fn producer(mut self, producer: impl Into<String>) -> Self {
+        self.info.producer = Some(producer.into());
+        self
+    }
+
+    /// Sets the `/CreationDate` entry of the document information dictionary. The date is written
+    /// as given and should follow the PDF date format, e.g. `D:20240131120000Z`.
+    pub fn creation_date(mut self, creation_date: impl Into<String>) -> Self {
+        self.info.creation_date = Some(creation_date.into());
+        self
+    }
+
+    /// Embeds an XMP metadata stream in the catalog, filled with the same values as the document
+    /// information dictionary.
+    pub fn xmp_metadata(mut self, enabled: bool) -> Self {
+        self.xmp_metadata = enabled;
+        self
+    }
+
+    /// Selects where the filler bytes are placed, see [`PaddingStrategy`].
+    pub fn padding(mut self, padding: PaddingStrategy) -> Self {
+        self.padding = padding;
+        self
+    }
+
+    /// Sets the `/MediaBox` of the page, A4 by default.
+    pub fn page_size(mut self, page_size: PageSize) -> Self {
+        self.page_size = page_size;
+        self
+    }
+
+    /// Prints a line of text in the top left corner of the page, readable unlike the padding.
+    /// Characters outside of Latin-1 are replaced by `?`, unless the caption is printed as
+    /// [Unicode](Options::unicode_caption).
+    pub fn caption(mut self, caption: impl Into<String>) -> Self {
+        self.caption = Some(caption.into());
+        self
+    }
+
+    /// Prints the [caption](Options::caption) as UCS-2 in one of the standard CJK fonts instead of
+    /// Latin-1, so captions in Chinese, Japanese or Korean are kept. The font is not embedded,
+    /// viewers provide it.
+    ///
+    /// Characters the character collection of `font` lacks, e.g. Arabic ones, are drawn as the
+    /// missing glyph, but text extraction still recovers them through the `/ToUnicode` map of the
+    /// font. Characters beyond the Basic Multilingual Plane are replaced by U+FFFD.
+    pub fn unicode_caption(mut self, font: CjkFont) -> Self {
+        self.unicode_caption = Some(font);
+        self
+    }
+
+    /// Stores the SHA-256 checksum of the padding in the document information dictionary under
+    /// [`CHECKSUM_KEY`](crate::checksum::CHECKSUM_KEY).
+    pub fn padding_checksum(mut self, enabled: bool) -> Self {
+        self.padding_checksum = enabled;
+        self
+    }
+
+    /// Guarantees byte-identical output: generating a document of the same size with the same
+    /// options always produces the same file, across runs, platforms and releases of this crate
+    /// with the same minor version. Object IDs are assigned in a fixed order, nothing depends on
+    /// the current time and the trailer gets a fixed `/ID` derived from the size and options, unless
+    /// another one is set with [`trailer_id`](Options::trailer_id).
+    pub fn deterministic(mut self, enabled: bool) -> Self {
+        self.deterministic = enabled;
+        self
+    }
+
+    /// Uses NUL bytes as filler instead of the digit `4`. Filesystems can store long runs of them as
+    /// holes, see [`SparseWriter`](crate::sparse::SparseWriter).
+    pub fn zero_filler(mut self, enabled: bool) -> Self {
+        self.zero_filler = enabled;
+        self
+    }
+
+    /// Splits the filler of [`PaddingStrategy::Text`], [`PaddingStrategy::HexText`] and
+    /// [`PaddingStrategy::CalibrationPage`] across several content streams holding at most `limit`
+    /// bytes of it each, which the page lists in a `/Contents` array. Some viewers struggle with a
+    /// single huge string.
+    pub fn content_stream_limit(mut self, limit: usize) -> Self {
+        self.content_stream_limit = Some(limit.max(1));
+        self
+    }
+
+    /// Sets the amount of pages, one by default. The padding is placed on the first page, all
+    /// others are blank.
+    pub fn page_count(mut self, count: usize) -> Self {
+        self.blank_pages = count.saturating_sub(1);
+        self
+    }
+
+    /// Sets the amount of text fields of [`PaddingStrategy::FormFields`], which share the filler
+    /// between their values. One by default.
+    pub fn form_fields(mut self, count: usize) -> Self {
+        self.form_fields = count.max(1);
+        self
+    }
+
+    /// Sets the amount of annotations [`PaddingStrategy::Annotations`] places on every page, which
+    /// share the filler between their contents. One by default.
+    pub fn annotations_per_page(mut self, count: usize) -> Self {
+        self.annotations_per_page = count.max(1);
+        self
+    }
+
+    /// Adds a document outline with `count` bookmarks, which jump to the pages in turn. None by
+    /// default.
+    pub fn bookmarks(mut self, count: usize) -> Self {
+        self.bookmarks = count;
+        self
+    }
+
+    /// Nests the bookmarks up to `depth` levels deep, see [`Options::bookmarks`]. All bookmarks are
+    /// at the top level by default.
+    pub fn bookmark_depth(mut self, depth: usize) -> Self {
+        self.bookmark_depth = depth.max(1);
+        self
+    }
+
+    /// Embeds JavaScript for testing security products: an `/OpenAction` and a document-level
+    /// script, which both only show an alert saying the document is a harmless test fixture.
+    /// Viewers with JavaScript enabled run them when the document is opened, and scanners are
+    /// expected to flag the document for them.
+    pub fn benign_javascript(mut self, enabled: bool) -> Self {
+        self.benign_javascript = enabled;
+        self
+    }
+
+    /// Adds an empty signature field whose `/Contents` reserves `len` bytes for a signature, so
+    /// external tools can sign the file in place. The `/ByteRange` already matches the saved file.
+    /// A detached CMS signature including a certificate chain usually fits into 8 KiB.
+    ///
+    /// Signatures cannot be combined with [incremental updates](crate::incremental), which would
+    /// not be covered by them.
+    pub fn signature_placeholder(mut self, len: usize) -> Self {
+        self.signature_placeholder = Some(len);
+        self
+    }
+
+    /// Sets the version in the header of the document, see [`PdfVersion`].
+    pub fn pdf_version(mut self, version: PdfVersion) -> Self {
+        self.pdf_version = version;
+        self
+    }
+
+    /// Selects the file identifier in the `/ID` entry of the trailer, see [`TrailerId`].
+    pub fn trailer_id(mut self, trailer_id: TrailerId) -> Self {
+        self.trailer_id = trailer_id;
+        self
+    }
+
+    /// Selects the end of the lines of the structure and the content stream, see [`LineEnding`].
+    /// Only the native writer of the `native-writer` feature supports anything but line feeds,
+    /// lopdf rejects any other ending with [`Error::InvalidOption`].
+    pub fn line_ending(mut self, line_ending: LineEnding) -> Self {
+        self.line_ending = line_ending;
+        self
+    }
+
+    /// Selects the two bytes ending every entry of the cross reference table, see
+    /// [`XrefEntryEnding`]. Like the [line ending](Options::line_ending), only the native writer
+    /// supports anything but the default.
+    pub fn xref_entry_ending(mut self, xref_entry_ending: XrefEntryEnding) -> Self {
+        self.xref_entry_ending = xref_entry_ending;
+        self
+    }
+
+    /// Defines and references every object with this generation number instead of 0. Like the
+    /// [line ending](Options::line_ending), only the native writer supports anything but 0.
+    pub fn object_generation(mut self, generation: u16) -> Self {
+        self.object_generation = generation;
+        self
+    }
+
+    /// Lists this many free objects in the cross reference table, numbered after the objects of the
+    /// document and linked into the free list headed by object 0. Like the
+    /// [line ending](Options::line_ending), only the native writer supports free objects.
+    pub fn free_objects(mut self, count: usize) -> Self {
+        self.free_objects = count;
+        self
+    }
+
+    /// Requires the size of every document to be a multiple of `size` bytes, e.g. the 4096 bytes
+    /// of a disk sector. Other sizes are rejected with [`Error::InvalidOption`] instead of being
+    /// changed, [`Options::align`] rounds them up.
+    pub fn block_size(mut self, size: usize) -> Self {
+        self.block_size = Some(size.max(1));
+        self
+    }
+
+    /// Gives every page a single content stream of exactly the given amount of bytes, one size per
+    /// [page](Options::page_count), e.g. ten pages of 64 KiB each. The streams are filled with
+    /// hidden text and the padding absorbs the rest of the size, which requires a padding strategy
+    /// keeping the filler out of the content streams: [`PaddingStrategy::FormFields`] or
+    /// [`PaddingStrategy::Annotations`]. The caption is printed by the stream of the first page.
+    /// Every stream takes at least 31 bytes, the operators around the text.
+    pub fn page_content_sizes(mut self, sizes: impl IntoIterator<Item = usize>) -> Self {
+        self.page_content_sizes = sizes.into_iter().collect();
+        self
+    }
+
+    /// Adds a content stream compressed with `FlateDecode` which decodes to exactly `len` bytes of
+    /// hidden text, e.g. a 1 MB file expanding to 500 MB, for testing decompression limits. The
+    /// compressed stream counts towards the size of the file like any other content and the padding
+    /// fills the rest. Filler compresses about a thousandfold, so a file needs roughly a thousandth of
+    /// `len` bytes to hold the stream. At least 31 bytes, the operators around the text.
+    pub fn decompressed_size(mut self, len: usize) -> Self {
+        self.decompressed_size = Some(len);
+        self
+    }
+
+    /// Encodes the content streams holding the filler with `filters`, listed in the order readers
+    /// decode them in, like the `/Filter` array of the streams. Each filter is a separate decoder
+    /// of a reader. Only the strategies placing the filler in content streams support filters:
+    /// [`PaddingStrategy::Text`], [`PaddingStrategy::HexText`] and
+    /// [`PaddingStrategy::CalibrationPage`]. See [`StreamFilter`] for how much room each filter
+    /// takes.
+    pub fn stream_filters(mut self, filters: impl IntoIterator<Item = StreamFilter>) -> Self {
+        self.stream_filters = filters.into_iter().collect();
+        self
+    }
+
+    /// Rounds `file_size_bytes` up to the next multiple of the [block size](Options::block_size).
+    pub fn align(&self, file_size_bytes: usize) -> usize {
+        match self.block_size {
+            Some(block_size) => file_size_bytes.next_multiple_of(block_size),
+            None => file_size_bytes,
+        }
+    }
+
+    /// Checks that a document of `file_size_bytes` bytes fills whole blocks.
+    pub(crate) fn validate_size(&self, file_size_bytes: usize) -> Result<(), Error> {
+        match self.block_size {
+            Some(block_size) if !file_size_bytes.is_multiple_of(block_size) => Err(Error::InvalidOption(format!(
+                "The requested size of {file_size_bytes} bytes is not a multiple of the block size of {block_size} bytes."
+            ))),
+            _ => Ok(()),
+        }
+    }
+
+    /// Checks that lopdf can write the document, it writes the line endings and the cross reference
+    /// section itself and numbers every object with generation 0.
+    pub(crate) fn validate_lopdf(&self) -> Result<(), Error> {
+        let defaults = Options::default();
+        if self.line_ending != defaults.line_ending || self.xref_entry_ending != defaults.xref_entry_ending {
+            return Err(Error::InvalidOption("Line endings other than line feeds require the native writer.".into()));
+        }
+        if self.object_generation != defaults.object_generation || self.free_objects != defaults.free_objects {
+            return Err(Error::InvalidOption("Generation numbers and free objects require the native writer.".into()));
+        }
+        Ok(())
+    }
+
+    /// Checks that all requested features can be represented in the selected PDF version.
+    pub(crate) fn validate(&self) -> Result<(), Error> {
+        if self.pdf_version >= PdfVersion::V2_0 && self.padding == PaddingStrategy::FormFields {
+            // the values of the fields are only displayed because viewers are asked to lay them out
+            return Err(Error::InvalidOption(
+                "The form-fields padding strategy relies on NeedAppearances, which PDF 2.0 deprecates.".into(),
+            ));
+        }
+        let text_padding =
+            matches!(self.padding, PaddingStrategy::Text | PaddingStrategy::HexText | PaddingStrategy::CalibrationPage);
+        if !self.stream_filters.is_empty() && !text_padding {
+            // filtering images would let completing a row of pixels shrink the document
+            return Err(Error::InvalidOption(
+                "Stream filters require a padding strategy placing the filler in content streams.".into(),
+            ));
+        }
+        if self.pdf_version >= PdfVersion::V2_0 && self.trailer_id == TrailerId::Omitted {
+            return Err(Error::InvalidOption("PDF 2.0 requires a file identifier in the trailer.".into()));
+        }
+        if !self.page_content_sizes.is_empty() {
+            let pages = self.blank_pages + 1;
+            if self.page_content_sizes.len() != pages {
+                return Err(Error::InvalidOption(format!(
+                    "Expected a content stream size for every page, {pages} sizes, got {}.",
+                    self.page_content_sizes.len()
+                )));
+            }
+            if !matches!(self.padding, PaddingStrategy::FormFields | PaddingStrategy::Annotations) {
+                return Err(Error::InvalidOption(
+                    "Content stream sizes require the form-fields or annotations padding strategy.".into(),
+                ));
+            }
+            if self.decompressed_size.is_some() {
+                // the compressed stream would be another content stream of the first page
+                return Err(Error::InvalidOption(
+                    "Content stream sizes cannot be combined with a decompressed size.".into(),
+                ));
+            }
+        }
+        if let Some(len) = self.decompressed_size.filter(|&len| len < MIN_DECOMPRESSED_SIZE) {
+            return Err(Error::InvalidOption(format!(
+                "The decompressed size of {len} bytes is below the minimum of {MIN_DECOMPRESSED_SIZE} bytes."
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// The version of the PDF specification a document complies with. The constructs written adapt to
+/// it: documents older than PDF 1.5 get a cross reference table instead of a cross reference stream,
+/// and PDF 2.0 documents always get a file identifier and keep only the dates in the deprecated
+/// document information dictionary, the other entries are stored as [XMP metadata](Options::xmp_metadata).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord)]
+pub enum PdfVersion {
+    V1_4,
+    #[default]
+    V1_5,
+    V1_6,
+    V1_7,
+    V2_0,
+}
+
+impl PdfVersion {
+    /// Returns the version as written in the header, e.g. `1.7`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            PdfVersion::V1_4 => "1.4",
+            PdfVersion::V1_5 => "1.5",
+            PdfVersion::V1_6 => "1.6",
+            PdfVersion::V1_7 => "1.7",
+            PdfVersion::V2_0 => "2.0",
+        }
+    }
+}
+
+/// Parses a version as written in the header, e.g. `1.7`.
+impl FromStr for PdfVersion {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "1.4" => Ok(PdfVersion::V1_4),
+            "1.5" => Ok(PdfVersion::V1_5),
+            "1.6" => Ok(PdfVersion::V1_6),
+            "1.7" => Ok(PdfVersion::V1_7),
+            "2.0" => Ok(PdfVersion::V2_0),
+            _ => Err(Error::InvalidOption(format!("Unsupported PDF version {s}, expected 1.4, 1.5, 1.6, 1.7 or 2.0."))),
+        }
+    }
+}
+
+/// The file identifier in the `/ID` entry of the trailer. It consists of the identifier of the
+/// original file and that of the current version, which are the same for a new document.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TrailerId {
+    /// Derived from the size and the options for [deterministic](Options::deterministic) documents
+    /// and PDF 2.0 documents, which require one, and omitted for any other document.
+    #[default]
+    Automatic,
+    /// The given bytes.
+    Fixed(Vec<u8>),
+    /// 16 bytes derived from the seed, regardless of the size and the options.
+    Seeded(u64),
+    /// No identifier, which PDF 2.0 does not allow.
+    Omitted,
+}
+
+/// Parses `auto`, `none`, `seed:<number>` or the identifier in hexadecimal digits.
+impl FromStr for TrailerId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let invalid = || {
+            Error::InvalidOption(format!(
+                "Invalid trailer ID {s}, expected auto, none, seed:<number> or an even amount of hexadecimal digits."
+            ))
+        };
+        let hex = !s.is_empty() && s.len().is_multiple_of(2) && s.bytes().all(|digit| digit.is_ascii_hexdigit());
+        match s {
+            "auto" => Ok(TrailerId::Automatic),
+            "none" => Ok(TrailerId::Omitted),
+            _ => match s.strip_prefix("seed:") {
+                Some(seed) => Ok(TrailerId::Seeded(seed.parse().map_err(|_| invalid())?)),
+                None if hex => {
+                    let byte = |index| u8::from_str_radix(&s[index..index + 2], 16).map_err(|_| invalid());
+                    Ok(TrailerId::Fixed((0..s.len()).step_by(2).map(byte).collect::<Result<_, _>>()?))
+                }
+                None => Err(invalid()),
+            },
+        }
+    }
+}
+
+/// The end of line marker written after every line of the structure, including the keywords around
+/// stream data and the operators of the content stream. Entries of the cross reference table are
+/// ended by the [`XrefEntryEnding`] instead.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LineEnding {
+    #[default]
+    LineFeed,
+    /// A carriage return followed by a line feed, which adds a byte to every line.
+    CarriageReturnLineFeed,
+}
+
+impl LineEnding {
+    /// Returns `text` with its line feeds replaced by this line ending.
+    pub fn apply(&self, text: &[u8]) -> Vec<u8> {
+        match self {
+            LineEnding::LineFeed => text.to_vec(),
+            LineEnding::CarriageReturnLineFeed => {
+                let mut converted = Vec::with_capacity(text.len());
+                for &byte in text {
+                    if byte == b'\n' {
+                        converted.push(b'\r');
+                    }
+                    converted.push(byte);
+                }
+                converted
+            }
+        }
+    }
+}
+
+/// Parses `lf` or `crlf`.
+impl FromStr for LineEnding {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "lf" => Ok(LineEnding::LineFeed),
+            "crlf" => Ok(LineEnding::CarriageReturnLineFeed),
+            _ => Err(Error::InvalidOption(format!("Unknown line ending {s}, expected lf or crlf."))),
+        }
+    }
+}
+
+/// The two bytes ending every entry of the cross reference table, which keeps every entry 20
+/// bytes long whichever of them the specification allows is chosen.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum XrefEntryEnding {
+    /// A space followed by a line feed.
+    #[default]
+    SpaceLineFeed,
+    /// A space followed by a carriage return.
+    SpaceCarriageReturn,
+    /// A carriage return followed by a line feed.
+    CarriageReturnLineFeed,
+}
+
+impl XrefEntryEnding {
+    /// Returns the two bytes as written after an entry.
+    pub fn as_bytes(&self) -> &'static [u8] {
+        match self {
+            XrefEntryEnding::SpaceLineFeed => b" \n",
+            XrefEntryEnding::SpaceCarriageReturn => b" \r",
+            XrefEntryEnding::CarriageReturnLineFeed => b"\r\n",
+        }
+    }
+}
+
+/// Parses `space-lf`, `space-cr` or `crlf`.
+impl FromStr for XrefEntryEnding {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "space-lf" => Ok(XrefEntryEnding::SpaceLineFeed),
+            "space-cr" => Ok(XrefEntryEnding::SpaceCarriageReturn),
+            "crlf" => Ok(XrefEntryEnding::CarriageReturnLineFeed),
+            _ => Err(Error::InvalidOption(format!(
+                "Unknown cross reference entry ending {s}, expected space-lf, space-cr or crlf."
+            ))),
+        }
+    }
+}
+
+impl Default for Options {
+    fn default() -> Self {
+        Options {
+            info: Info::default(),
+            xmp_metadata: false,
+            padding: PaddingStrategy::default(),
+            page_size: PageSize::default(),
+            caption: None,
+            unicode_caption: None,
+            padding_checksum: false,
+            deterministic: false,
+            zero_filler: false,
+            content_stream_limit: None,
+            blank_pages: 0,
+            page_content_sizes: Vec::new(),
+            form_fields: 1,
+            annotations_per_page: 1,
+            bookmarks: 0,
+            bookmark_depth: 1,
+            benign_javascript: false,
+            signature_placeholder: None,
+            pdf_version: PdfVersion::default(),
+            block_size: None,
+            decompressed_size: None,
+            stream_filters: Vec::new(),
+            trailer_id: TrailerId::default(),
+            line_ending: LineEnding::default(),
+            xref_entry_ending: XrefEntryEnding::default(),
+            object_generation: 0,
+            free_objects: 0,
+            nesting_placeholder: None,
+        }
+    }
+}
+
+/// The size of the page in points (1/72 inch).
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PageSize {
+    #[default]
+    A4,
+    Letter,
+    Legal,
+    A3,
+    Custom { width: u32, height: u32 },
+}
+
+impl PageSize {
+    /// Returns width and height in points.
+    pub fn dimensions(&self) -> (i64, i64) {
+        match *self {
+            PageSize::A4 => (595, 842),
+            PageSize::Letter => (612, 792),
+            PageSize::Legal => (612, 1008),
+            PageSize::A3 => (842, 1191),
+            PageSize::Custom { width, height } => (width as i64, height as i64),
+        }
+    }
+}
+
+/// Parses either the name of a preset (case insensitive) or `<width>x<height>` in points.
+impl FromStr for PageSize {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_ascii_lowercase().as_str() {
+            "a4" => Ok(PageSize::A4),
+            "letter" => Ok(PageSize::Letter),
+            "legal" => Ok(PageSize::Legal),
+            "a3" => Ok(PageSize::A3),
+            custom => {
+                let parse = |(width, height): (&str, &str)| Some((width.parse().ok()?, height.parse().ok()?));
+                match custom.split_once('x').and_then(parse) {
+                    Some((width, height)) if width > 0 && height > 0 => Ok(PageSize::Custom { width, height }),
+                    _ => Err(Error::InvalidOption(format!(
+                        "Unknown page size {s}, expected A4, Letter, Legal, A3 or <width>x<height> in points."
+                    ))),
+                }
+            }
+        }
+    }
+}
+
+/// The entries of the document information dictionary.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub(crate) struct Info {
+    pub title: Option<String>,
+    pub author: Option<String>,
+    pub subject: Option<String>,
+    pub producer: Option<String>,
+    pub creation_date: Option<String>,
+}
+
+impl Info {
+    /// Returns only the entries PDF 2.0 has not deprecated.
+    pub fn dates(&self) -> Info {
+        Info { creation_date: self.creation_date.clone(), ..Info::default() }
+    }
+
+    /// Whether any entry other than the dates is set.
+    pub fn has_descriptive_entries(&self) -> bool {
+        [&self.title, &self.author, &self.subject, &self.producer].iter().any(|entry| entry.is_some())
+    }
+
+    /// Returns the information dictionary, or `None` if no entry has been set.
+    pub fn to_dictionary(&self) -> Option<Dictionary> {
+        let entries = [
+            ("Title", &self.title),
+            ("Author", &self.author),
+            ("Subject", &self.subject),
+            ("Producer", &self.producer),
+            ("CreationDate", &self.creation_date),
+        ];
+
+        let mut dict = Dictionary::new();
+        for (key, value) in entries {
+            if let Some(value) = value {
+                dict.set(key, text_string(value));
+            }
+        }
+        (!dict.is_empty()).then_some(dict)
+    }
+}
+
+/// Encodes `text` as a PDF text string. Anything but ASCII is written as UTF-16BE.
+pub(crate) fn text_string(text: &str) -> Object {
+    let bytes = if text.is_ascii() {
+        text.as_bytes().to_vec()
+    } else {
+        lopdf::encode_utf16_be(text)
+    };
+    Object::String(bytes, StringFormat::Literal)
+}
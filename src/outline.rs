@@ -0,0 +1,108 @@
+//! Document outlines (bookmarks) shown in the navigation pane of viewers.
+
+use lopdf::{dictionary, Document, Object, ObjectId};
+
+use crate::options::text_string;
+
+/// Adds an outline of `bookmarks` items nested up to `depth` levels to `doc` and returns the ID of
+/// its root. The items are numbered in reading order and each one jumps to the next page in turn.
+///
+/// Every item at the top level starts a chain of nested items, so the levels are used as evenly as
+/// the amount of bookmarks allows: with a depth of 3, items 1, 4, 7, … are at the top level, items
+/// 2, 5, 8, … below them and items 3, 6, 9, … below those. `bookmarks` and `depth` must not be 0.
+pub(crate) fn add_outline(doc: &mut Document, bookmarks: usize, depth: usize, page_ids: &[ObjectId]) -> ObjectId {
+    let root_id = doc.new_object_id();
+    let ids: Vec<ObjectId> = (0..bookmarks).map(|_| doc.new_object_id()).collect();
+
+    let root = dictionary! {
+        "Type" => "Outlines",
+        "First" => ids[0],
+        "Last" => ids[(bookmarks - 1) / depth * depth],
+        // all items are open, so every one of them is visible
+        "Count" => bookmarks as i64,
+    };
+    doc.objects.insert(root_id, Object::Dictionary(root));
+
+    for (index, &id) in ids.iter().enumerate() {
+        let level = index % depth;
+        let mut item = dictionary! {
+            "Title" => text_string(&format!("Bookmark {}", index + 1)),
+            "Parent" => if level == 0 { root_id } else { ids[index - 1] },
+            "Dest" => vec![page_ids[index % page_ids.len()].into(), "Fit".into()],
+        };
+        // only the items at the top level have siblings
+        if level == 0 {
+            if index >= depth {
+                item.set("Prev", ids[index - depth]);
+            }
+            if let Some(&next) = ids.get(index + depth) {
+                item.set("Next", next);
+            }
+        }
+        // the count of an open item is the amount of its descendants
+        let descendants = (depth - 1 - level).min(bookmarks - 1 - index);
+        if descendants > 0 {
+            item.set("First", ids[index + 1]);
+            item.set("Last", ids[index + 1]);
+            item.set("Count", descendants as i64);
+        }
+        doc.objects.insert(id, Object::Dictionary(item));
+    }
+
+    root_id
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::{Dictionary, Document};
+
+    use crate::verify::{verify_pdf_bytes, SizeTarget};
+    use crate::{generate_pdf_bytes, minimum_size, Options, PaddingStrategy};
+
+    /// Walks the items below `parent` in reading order, collecting their titles and the deepest
+    /// level reached. Returns the amount of items below `parent`.
+    fn walk(doc: &Document, parent: &Dictionary, level: usize, titles: &mut Vec<String>, deepest: &mut usize) -> usize {
+        let Ok(first) = parent.get(b"First") else {
+            return 0;
+        };
+        *deepest = (*deepest).max(level);
+        let mut items = 0;
+        let mut next = Some(first.as_reference().unwrap());
+        while let Some(id) = next {
+            let item = doc.get_dictionary(id).unwrap();
+            titles.push(String::from_utf8(item.get(b"Title").unwrap().as_str().unwrap().to_vec()).unwrap());
+            let page = item.get(b"Dest").unwrap().as_array().unwrap()[0].as_reference().unwrap();
+            assert!(doc.get_pages().values().any(|&id| id == page), "bookmark to {page:?}, which is not a page");
+            let descendants = walk(doc, item, level + 1, titles, deepest);
+            assert_eq!(item.get(b"Count").map_or(0, |count| count.as_i64().unwrap()), descendants as i64);
+            items += 1 + descendants;
+            next = item.get(b"Next").ok().map(|next| next.as_reference().unwrap());
+        }
+        items
+    }
+
+    #[test]
+    fn every_size_with_an_outline() {
+        for (bookmarks, depth, pages) in [(1, 1, 1), (5, 1, 2), (7, 3, 3), (10, 4, 1), (3, 8, 2)] {
+            for padding in [PaddingStrategy::Text, PaddingStrategy::Annotations] {
+                let options =
+                    Options::default().padding(padding).bookmarks(bookmarks).bookmark_depth(depth).page_count(pages);
+                let minimum = minimum_size(&options).unwrap();
+                for size in (minimum..minimum + 16).chain(10_000 - 16..10_000 + 16) {
+                    let bytes = generate_pdf_bytes(size, &options).unwrap();
+                    assert!(verify_pdf_bytes(&bytes, SizeTarget::Exact(size)).is_valid(), "{options:?} {size}");
+
+                    let doc = Document::load_mem(&bytes).unwrap();
+                    let root = doc.catalog().unwrap().get(b"Outlines").unwrap().as_reference().unwrap();
+                    let root = doc.get_dictionary(root).unwrap();
+                    let (mut titles, mut deepest) = (Vec::new(), 0);
+                    assert_eq!(walk(&doc, root, 1, &mut titles, &mut deepest), bookmarks);
+                    assert_eq!(root.get(b"Count").unwrap().as_i64().unwrap(), bookmarks as i64);
+                    let expected: Vec<String> = (1..=bookmarks).map(|number| format!("Bookmark {number}")).collect();
+                    assert_eq!(titles, expected);
+                    assert_eq!(deepest, depth.min(bookmarks), "{options:?}");
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,568 @@
+//! The different ways of placing the filler bytes on the page.
+
+use std::str::FromStr;
+
+use lopdf::content::Operation;
+use lopdf::{dictionary, Dictionary, Object, Stream, StringFormat};
+
+use crate::generator::Scratch;
+use crate::{content_stream, fill, filler_byte, unicode, Error, Options, HIDDEN_TEXT_END, HIDDEN_TEXT_START};
+
+/// Where the filler bytes end up in the document.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PaddingStrategy {
+    /// An invisible string in the page content stream.
+    #[default]
+    Text,
+    /// An invisible hexadecimal string in the page content stream, which takes two bytes for every
+    /// byte of filler.
+    HexText,
+    /// An uncompressed greyscale image covering the page, like an unprocessed scan.
+    RawImage,
+    /// A JPEG (`DCTDecode`) image covering the page, like a scanned document. The filler is stored
+    /// in comment segments of the JPEG.
+    JpegImage,
+    /// The values of text fields in an interactive form (AcroForm) on the page, see
+    /// [`Options::form_fields`]. Viewers lay out the values themselves, so the filler reaches the
+    /// form filling and flattening code of a reader instead of the content stream interpreter.
+    FormFields,
+    /// The `/Contents` of text notes and links on every page, see [`Options::annotations_per_page`].
+    /// The filler is shared between all of them evenly.
+    Annotations,
+    /// An invisible string like [`PaddingStrategy::Text`] below a print calibration sheet scaled
+    /// to the page: a centimetre grid, crop marks at the corners of a rectangle 10 mm inside the
+    /// edges, millimetre rulers along its bottom and left edges and a 50 mm square around a cross
+    /// in the centre. The drawing takes about 16 KB on an A4 page.
+    CalibrationPage,
+}
+
+impl PaddingStrategy {
+    pub const ALL: [PaddingStrategy; 7] = [
+        PaddingStrategy::Text,
+        PaddingStrategy::HexText,
+        PaddingStrategy::RawImage,
+        PaddingStrategy::JpegImage,
+        PaddingStrategy::FormFields,
+        PaddingStrategy::Annotations,
+        PaddingStrategy::CalibrationPage,
+    ];
+}
+
+/// Parses the name of a strategy in kebab case, e.g. `jpeg-image`.
+impl FromStr for PaddingStrategy {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "text" => Ok(PaddingStrategy::Text),
+            "hex-text" => Ok(PaddingStrategy::HexText),
+            "raw-image" => Ok(PaddingStrategy::RawImage),
+            "jpeg-image" => Ok(PaddingStrategy::JpegImage),
+            "form-fields" => Ok(PaddingStrategy::FormFields),
+            "annotations" => Ok(PaddingStrategy::Annotations),
+            "calibration-page" => Ok(PaddingStrategy::CalibrationPage),
+            _ => Err(Error::InvalidOption(format!(
+                "Unknown padding strategy {s}, expected text, hex-text, raw-image, jpeg-image, form-fields, annotations or \
+                calibration-page."
+            ))),
+        }
+    }
+}
+
+/// Name of the image in the page resources.
+pub(crate) const IMAGE_NAME: &str = "Im1";
+
+/// Width of the raw image in pixels, one for every point across an A4 page.
+const RAW_IMAGE_WIDTH: usize = 595;
+/// The light grey of a scanned sheet of paper.
+const PAPER_GREY: u8 = 0xEE;
+
+/// Maximum size of a JPEG comment segment including its marker and length.
+const MAX_JPEG_COMMENT: usize = 2 + 0xFFFF;
+
+/// The page content streams along with the XObjects they draw and the form fields on the page. Only
+/// the annotations are placed on the further pages as well, listed page by page.
+pub(crate) struct PageContent {
+    pub contents: Vec<ContentStream>,
+    pub xobjects: Vec<(&'static str, Stream)>,
+    pub fields: Vec<Dictionary>,
+    pub annotations: Vec<Vec<Dictionary>>,
+}
+
+/// The operations of a content stream and the hidden text among them. The string of the hidden text
+/// is only a length, the filler is written straight into the stream data, see
+/// [`crate::content_stream`].
+pub(crate) struct ContentStream {
+    pub operations: Vec<Operation>,
+    pub hidden_text: Option<HiddenText>,
+}
+
+impl From<Vec<Operation>> for ContentStream {
+    fn from(operations: Vec<Operation>) -> Self {
+        ContentStream { operations, hidden_text: None }
+    }
+}
+
+/// Invisible text showing `len` bytes of filler, printed before the operation at `index`.
+pub(crate) struct HiddenText {
+    pub index: usize,
+    pub len: usize,
+    pub format: StringFormat,
+}
+
+/// Places `filler` bytes on the page using the padding strategy of `options`.
+///
+/// A larger filler never produces a smaller document, which [`crate::sizing`] relies on.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "fill", level = "trace", skip_all, fields(filler_len = filler_len))
+)]
+pub(crate) fn page_content(options: &Options, filler_len: usize, scratch: &mut Scratch) -> Result<PageContent, Error> {
+    match options.padding {
+        PaddingStrategy::Text | PaddingStrategy::HexText => {
+            let format = match options.padding {
+                PaddingStrategy::HexText => StringFormat::Hexadecimal,
+                _ => StringFormat::Literal,
+            };
+            let contents = text_contents(options, filler_len, format);
+            Ok(PageContent { contents, xobjects: Vec::new(), fields: Vec::new(), annotations: Vec::new() })
+        }
+        PaddingStrategy::CalibrationPage => {
+            let mut contents = text_contents(options, filler_len, StringFormat::Literal);
+            let calibration = calibration(options);
+            if let Some(hidden_text) = &mut contents[0].hidden_text {
+                hidden_text.index += calibration.len();
+            }
+            contents[0].operations.splice(0..0, calibration);
+            Ok(PageContent { contents, xobjects: Vec::new(), fields: Vec::new(), annotations: Vec::new() })
+        }
+        PaddingStrategy::RawImage => raw_image(options, filler_len, scratch),
+        PaddingStrategy::JpegImage => Ok(PageContent {
+            contents: vec![[draw_image(options), caption(options)].concat().into()],
+            xobjects: vec![(IMAGE_NAME, image(JPEG_SIZE, JPEG_SIZE, Some("DCTDecode"), jpeg(filler_len, options, scratch)))],
+            fields: Vec::new(),
+            annotations: Vec::new(),
+        }),
+        PaddingStrategy::FormFields => Ok(PageContent {
+            contents: vec![caption(options).into()],
+            xobjects: Vec::new(),
+            fields: form_fields(options, filler_len, scratch),
+            annotations: Vec::new(),
+        }),
+        PaddingStrategy::Annotations => Ok(PageContent {
+            contents: vec![caption(options).into()],
+            xobjects: Vec::new(),
+            fields: Vec::new(),
+            annotations: annotations(options, filler_len, scratch),
+        }),
+    }
+}
+
+/// Completes the data of the content stream of the page with the index `page` with hidden text, so
+/// it is exactly `len` bytes long, see [`Options::page_content_sizes`].
+pub(crate) fn sized_content(
+    mut content: Vec<u8>,
+    len: usize,
+    page: usize,
+    options: &Options,
+) -> Result<Vec<u8>, Error> {
+    if !content.is_empty() {
+        content.push(b'\n');
+    }
+    let minimum = content.len() + HIDDEN_TEXT_START.len() + HIDDEN_TEXT_END.len();
+    if len < minimum {
+        return Err(Error::InvalidOption(format!(
+            "The content stream of page {} takes at least {minimum} bytes, {len} bytes were requested.",
+            page + 1
+        )));
+    }
+    content.extend_from_slice(HIDDEN_TEXT_START);
+    content.resize(len - HIDDEN_TEXT_END.len(), filler_byte(options));
+    content.extend_from_slice(HIDDEN_TEXT_END);
+    Ok(content)
+}
+
+/// The hidden text holding `filler_len` bytes, split into content streams according to
+/// [`Options::content_stream_limit`]. The caption is printed by the last one.
+fn text_contents(options: &Options, filler_len: usize, format: StringFormat) -> Vec<ContentStream> {
+    let mut contents: Vec<_> = chunks(filler_len, options.content_stream_limit)
+        .map(|len| ContentStream { operations: Vec::new(), hidden_text: Some(HiddenText { index: 0, len, format }) })
+        .collect();
+    contents.last_mut().expect("there is at least one chunk").operations.extend(caption(options));
+    contents
+}
+
+/// Splits `len` filler bytes into chunks of at most `limit` bytes. The chunks are filled one after
+/// another, so another byte of filler either grows the last chunk or adds a new one.
+fn chunks(len: usize, limit: Option<usize>) -> impl Iterator<Item = usize> {
+    let limit = limit.unwrap_or(usize::MAX);
+    let count = len.div_ceil(limit).max(1);
+    (0..count).map(move |chunk| (len - chunk * limit).min(limit))
+}
+
+/// The image always has at least one row of pixels. An image can only grow by full rows, so the
+/// remainder is printed as hidden text. To keep the `/Length` of the content stream from shrinking
+/// whenever another row is completed, the text is prefixed so the length always has the same
+/// amount of digits.
+fn raw_image(options: &Options, filler_len: usize, scratch: &mut Scratch) -> Result<PageContent, Error> {
+    let rows = 1 + filler_len / RAW_IMAGE_WIDTH;
+    let remainder = filler_len % RAW_IMAGE_WIDTH;
+
+    let unpadded_len = content_stream(raw_image_operations(options, 0), options, scratch)?.content.len();
+    let mut magnitude = 1;
+    while magnitude < unpadded_len || magnitude + RAW_IMAGE_WIDTH > magnitude * 10 {
+        magnitude *= 10;
+    }
+
+    let pixels = scratch.bytes(RAW_IMAGE_WIDTH * rows, PAPER_GREY);
+    Ok(PageContent {
+        contents: vec![raw_image_operations(options, magnitude - unpadded_len + remainder)],
+        xobjects: vec![(IMAGE_NAME, image(RAW_IMAGE_WIDTH, rows, None, pixels))],
+        fields: Vec::new(),
+        annotations: Vec::new(),
+    })
+}
+
+fn raw_image_operations(options: &Options, text_len: usize) -> ContentStream {
+    let draw_image = draw_image(options);
+    let hidden_text = HiddenText { index: draw_image.len(), len: text_len, format: StringFormat::Literal };
+    ContentStream { operations: [draw_image, caption(options)].concat(), hidden_text: Some(hidden_text) }
+}
+
+/// Draws the image stretched across the whole page.
+fn draw_image(options: &Options) -> Vec<Operation> {
+    let (width, height) = options.page_size.dimensions();
+    vec![
+        // q saves the graphics state, so the transformation only applies to the image
+        Operation::new("q", vec![]),
+        // images are drawn into the unit square, cm scales it to the page
+        Operation::new("cm", vec![width.into(), 0.into(), 0.into(), height.into(), 0.into(), 0.into()]),
+        Operation::new("Do", vec![IMAGE_NAME.into()]),
+        Operation::new("Q", vec![]),
+    ]
+}
+
+/// One millimetre in points.
+const MM: f32 = 72.0 / 25.4;
+/// Distance of the corners marked by the crop marks from the edges of the page, in millimetres.
+const CROP_INSET: i64 = 10;
+/// Length of the crop marks and the gap between them and the marked corners, in millimetres.
+const CROP_MARK: i64 = 5;
+const CROP_GAP: i64 = 2;
+/// Edge length of the square in the centre of the page, in millimetres.
+const CENTRE_SQUARE: i64 = 50;
+const RULER_FONT_SIZE: i64 = 6;
+
+/// Draws the calibration sheet of [`PaddingStrategy::CalibrationPage`]. All distances are measured
+/// from the bottom left corner of the page in whole millimetres, so the sheet can be checked with
+/// a ruler after printing at actual size.
+fn calibration(options: &Options) -> Vec<Operation> {
+    let (width, height) = options.page_size.dimensions();
+    let (width_mm, height_mm) = ((width as f32 / MM) as i64, (height as f32 / MM) as i64);
+    // coordinates are rounded to a hundredth of a point, far below what a printer resolves
+    let point = |mm: i64| Object::Real((mm as f32 * MM * 100.0).round() / 100.0);
+    let mut operations = vec![Operation::new("q", vec![])];
+    let line = |operations: &mut Vec<Operation>, (x1, y1): (i64, i64), (x2, y2): (i64, i64)| {
+        operations.push(Operation::new("m", vec![point(x1), point(y1)]));
+        operations.push(Operation::new("l", vec![point(x2), point(y2)]));
+    };
+
+    // a light grey centimetre grid across the whole page
+    operations.push(Operation::new("G", vec![Object::Real(0.75)]));
+    operations.push(Operation::new("w", vec![Object::Real(0.25)]));
+    for x in (0..=width_mm).step_by(10) {
+        line(&mut operations, (x, 0), (x, height_mm));
+    }
+    for y in (0..=height_mm).step_by(10) {
+        line(&mut operations, (0, y), (width_mm, y));
+    }
+    operations.push(Operation::new("S", vec![]));
+
+    // black rulers along the bottom and left edges of the inset rectangle, which printers reach
+    // unlike the edges of the page, with longer ticks every 5 and 10 millimetres
+    let (left, bottom, right, top) = (CROP_INSET, CROP_INSET, width_mm - CROP_INSET, height_mm - CROP_INSET);
+    operations.push(Operation::new("G", vec![0.into()]));
+    operations.push(Operation::new("w", vec![Object::Real(0.5)]));
+    let tick = |mm: i64| match mm % 10 {
+        0 => 5,
+        5 => 3,
+        _ => 2,
+    };
+    for x in left..=right {
+        line(&mut operations, (x, bottom), (x, bottom + tick(x)));
+    }
+    for y in bottom..=top {
+        line(&mut operations, (left, y), (left + tick(y), y));
+    }
+
+    // crop marks outside the corners of the inset rectangle, pointing along its edges
+    let corners = [(left, bottom, -1, -1), (right, bottom, 1, -1), (left, top, -1, 1), (right, top, 1, 1)];
+    for (x, y, outwards_x, outwards_y) in corners {
+        let (near_x, far_x) = (x + outwards_x * CROP_GAP, x + outwards_x * (CROP_GAP + CROP_MARK));
+        let (near_y, far_y) = (y + outwards_y * CROP_GAP, y + outwards_y * (CROP_GAP + CROP_MARK));
+        line(&mut operations, (near_x, y), (far_x, y));
+        line(&mut operations, (x, near_y), (x, far_y));
+    }
+
+    // a cross through the centre inside a square, which reveals any distortion of the aspect ratio
+    let (centre_x, centre_y, half) = (width_mm / 2, height_mm / 2, CENTRE_SQUARE / 2);
+    line(&mut operations, (centre_x - half, centre_y), (centre_x + half, centre_y));
+    line(&mut operations, (centre_x, centre_y - half), (centre_x, centre_y + half));
+    operations.push(Operation::new("S", vec![]));
+    operations.push(Operation::new(
+        "re",
+        vec![point(centre_x - half), point(centre_y - half), point(CENTRE_SQUARE), point(CENTRE_SQUARE)],
+    ));
+    operations.push(Operation::new("S", vec![]));
+
+    // the centimetres are labelled above and beside the long ticks, in the font of the hidden text
+    operations.push(Operation::new("BT", vec![]));
+    operations.push(Operation::new("Tf", vec!["F1".into(), RULER_FONT_SIZE.into()]));
+    let mut position = (0, 0);
+    // the first centimetre inside the rectangle is the first one labelled, the corner stays free
+    let first_label = (CROP_INSET / 10 + 1) * 10;
+    let bottom_labels = (first_label..=right).step_by(10).map(|x| (x, bottom + 6, x / 10));
+    let left_labels = (first_label..=top).step_by(10).map(|y| (left + 6, y, y / 10));
+    for (x, y, label) in bottom_labels.chain(left_labels) {
+        // Td moves relative to the previous label
+        operations.push(Operation::new("Td", vec![point(x - position.0), point(y - position.1)]));
+        operations.push(Operation::new("Tj", vec![Object::string_literal(label.to_string())]));
+        position = (x, y);
+    }
+    operations.push(Operation::new("ET", vec![]));
+    operations.push(Operation::new("Q", vec![]));
+    operations
+}
+
+/// Multiline text fields wrap their value instead of showing a single line of it.
+const MULTILINE_FIELD: i64 = 1 << 12;
+/// The default appearance of the field values, the font of the hidden text at automatic size.
+pub(crate) const FIELD_APPEARANCE: &str = "/F1 0 Tf 0 g";
+
+/// Text fields sharing `filler_len` bytes of filler between their values evenly. They are stacked
+/// below the caption, each spanning the width of the page between the margins.
+fn form_fields(options: &Options, filler_len: usize, scratch: &mut Scratch) -> Vec<Dictionary> {
+    let count = options.form_fields;
+    let (width, height) = options.page_size.dimensions();
+    let top = height - 2 * CAPTION_MARGIN;
+    let row = ((top - CAPTION_MARGIN) / count as i64).max(1);
+
+    (0..count)
+        .map(|index| {
+            // every field is a widget annotation at the same time
+            let len = filler_len / count + usize::from(index < filler_len % count);
+            let field_top = top - index as i64 * row;
+            dictionary! {
+                "Type" => "Annot",
+                "Subtype" => "Widget",
+                "FT" => "Tx",
+                "T" => Object::string_literal(format!("field{}", index + 1)),
+                "Ff" => MULTILINE_FIELD,
+                "Rect" => vec![CAPTION_MARGIN.into(), (field_top - row).into(), (width - CAPTION_MARGIN).into(), field_top.into()],
+                "DA" => Object::string_literal(FIELD_APPEARANCE),
+                "V" => Object::String(scratch.filler(len, options), StringFormat::Literal),
+            }
+        })
+        .collect()
+}
+
+/// Edge length of the icons of annotations, and the distance between them.
+const ANNOTATION_SIZE: i64 = 24;
+/// Target of the links, a domain reserved for examples.
+const LINK_URI: &str = "https://example.com/";
+
+/// Text notes alternating with links, placed in rows along the bottom of every page. The filler is
+/// shared between their `/Contents` evenly, which are displayed as pop-up text and alternate
+/// descriptions respectively.
+fn annotations(options: &Options, filler_len: usize, scratch: &mut Scratch) -> Vec<Vec<Dictionary>> {
+    let per_page = options.annotations_per_page;
+    let count = per_page * (options.blank_pages + 1);
+    let (width, _) = options.page_size.dimensions();
+    let columns = ((width - 2 * CAPTION_MARGIN) / ANNOTATION_SIZE).max(1) as usize;
+
+    let mut annotation = |index: usize| {
+        let position = index % per_page;
+        let x = CAPTION_MARGIN + (position % columns) as i64 * ANNOTATION_SIZE;
+        let y = CAPTION_MARGIN + (position / columns) as i64 * ANNOTATION_SIZE;
+        let len = filler_len / count + usize::from(index < filler_len % count);
+        let mut annotation = dictionary! {
+            "Type" => "Annot",
+            "Rect" => vec![x.into(), y.into(), (x + ANNOTATION_SIZE).into(), (y + ANNOTATION_SIZE).into()],
+            "Contents" => Object::String(scratch.filler(len, options), StringFormat::Literal),
+        };
+        if position.is_multiple_of(2) {
+            annotation.set("Subtype", "Text");
+            annotation.set("Name", "Note");
+        } else {
+            annotation.set("Subtype", "Link");
+            annotation.set("Border", vec![0.into(), 0.into(), 0.into()]);
+            annotation.set("A", dictionary! { "S" => "URI", "URI" => Object::string_literal(LINK_URI) });
+        }
+        annotation
+    };
+    (0..=options.blank_pages)
+        .map(|page| (page * per_page..(page + 1) * per_page).map(&mut annotation).collect())
+        .collect()
+}
+
+/// Name of the font the caption is printed in.
+pub(crate) const CAPTION_FONT: &str = "F2";
+const CAPTION_FONT_SIZE: i64 = 12;
+/// Distance of the caption from the top left corner of the page, one inch.
+const CAPTION_MARGIN: i64 = 72;
+
+/// Prints the caption of `options`, if there is one, on top of everything else.
+fn caption(options: &Options) -> Vec<Operation> {
+    let Some(caption) = &options.caption else {
+        return Vec::new();
+    };
+    let (_, height) = options.page_size.dimensions();
+
+    let text = if options.unicode_caption.is_some() {
+        Object::String(unicode::encode(caption), StringFormat::Hexadecimal)
+    } else {
+        // WinAnsiEncoding matches Latin-1 for all printable characters of the latter
+        let text = caption
+            .chars()
+            .map(|char| match char as u32 {
+                code @ (0x20..=0x7E | 0xA0..=0xFF) => code as u8,
+                _ => b'?',
+            })
+            .collect();
+        Object::String(text, StringFormat::Literal)
+    };
+
+    vec![
+        Operation::new("BT", vec![]),
+        Operation::new("Tf", vec![CAPTION_FONT.into(), CAPTION_FONT_SIZE.into()]),
+        Operation::new("Td", vec![CAPTION_MARGIN.into(), (height - CAPTION_MARGIN).into()]),
+        Operation::new("Tj", vec![text]),
+        Operation::new("ET", vec![]),
+    ]
+}
+
+fn image(width: usize, height: usize, filter: Option<&str>, data: Vec<u8>) -> Stream {
+    let mut dict = dictionary! {
+        "Type" => "XObject",
+        "Subtype" => "Image",
+        "Width" => width as i64,
+        "Height" => height as i64,
+        "ColorSpace" => "DeviceGray",
+        "BitsPerComponent" => 8,
+    };
+    if let Some(filter) = filter {
+        dict.set("Filter", filter);
+    }
+    // compressing an image would defeat the size calculation
+    Stream::new(dict, data).with_compression(false)
+}
+
+/// Edge length of the JPEG image, a single 8x8 block.
+const JPEG_SIZE: usize = 8;
+
+/// A baseline greyscale JPEG consisting of a single block in [`PAPER_GREY`], padded with `padding`
+/// bytes of comments.
+fn jpeg(padding: usize, options: &Options, scratch: &mut Scratch) -> Vec<u8> {
+    let mut jpeg = scratch.buffer(padding + JPEG_FRAME.len() + 4);
+    jpeg.extend([0xFF, 0xD8]); // start of image
+
+    if padding < 4 {
+        // too small for a comment, but markers may be preceded by any amount of 0xFF fill bytes
+        jpeg.extend(JPEG_FRAME);
+        jpeg.extend(std::iter::repeat_n(0xFF, padding));
+    } else {
+        let segments = padding.div_ceil(MAX_JPEG_COMMENT);
+        for segment in 0..segments {
+            // distribute the padding evenly, so no segment is too small for its header
+            let segment_len = padding / segments + usize::from(segment < padding % segments);
+            jpeg.extend([0xFF, 0xFE]);
+            jpeg.extend(((segment_len - 2) as u16).to_be_bytes());
+            let start = jpeg.len();
+            jpeg.resize(start + segment_len - 4, 0);
+            if !scratch.measuring() {
+                fill(&mut jpeg[start..], options);
+            }
+        }
+        jpeg.extend(JPEG_FRAME);
+    }
+
+    jpeg.extend([0xFF, 0xD9]); // end of image
+    jpeg
+}
+
+/// Everything between the start and end of image markers of the padded JPEG.
+#[rustfmt::skip]
+const JPEG_FRAME: [u8; 138] = [
+    // quantization table 0, all ones
+    0xFF, 0xDB, 0x00, 0x43, 0x00,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1, 1,
+    // baseline frame, 8 bits, 8x8 pixels, one component
+    0xFF, 0xC0, 0x00, 0x0B, 0x08, 0x00, 0x08, 0x00, 0x08, 0x01, 0x01, 0x11, 0x00,
+    // DC huffman table 0, a single one bit code for the category 10
+    0xFF, 0xC4, 0x00, 0x14, 0x00, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x0A,
+    // AC huffman table 0, a single one bit code for end of block
+    0xFF, 0xC4, 0x00, 0x14, 0x10, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x00,
+    // start of scan
+    0xFF, 0xDA, 0x00, 0x08, 0x01, 0x01, 0x00, 0x00, 0x3F, 0x00,
+    // DC coefficient 880 = 8 * (0xEE - 128), followed by end of block and padding ones
+    0b0110_1110, 0b0000_1111,
+];
+
+#[cfg(test)]
+mod tests {
+    use super::{MAX_JPEG_COMMENT, RAW_IMAGE_WIDTH};
+    use crate::sizing::tests::assert_exact_sizes;
+    use crate::{minimum_size, Options, PaddingStrategy};
+
+    #[test]
+    fn every_size_around_another_row_of_pixels() {
+        let options = Options::default().padding(PaddingStrategy::RawImage);
+        let minimum = minimum_size(&options).unwrap();
+        assert_exact_sizes(&options, minimum);
+        assert_exact_sizes(&options, minimum + RAW_IMAGE_WIDTH - 32);
+        assert_exact_sizes(&options, minimum + 20 * RAW_IMAGE_WIDTH - 32);
+    }
+
+    #[test]
+    fn every_size_around_another_jpeg_comment() {
+        let options = Options::default().padding(PaddingStrategy::JpegImage);
+        let minimum = minimum_size(&options).unwrap();
+        assert_exact_sizes(&options, minimum);
+        assert_exact_sizes(&options, minimum + MAX_JPEG_COMMENT - 32);
+    }
+
+    #[test]
+    fn every_size_around_another_content_stream() {
+        for padding in [PaddingStrategy::Text, PaddingStrategy::CalibrationPage] {
+            let options = Options::default().padding(padding).caption("caption").content_stream_limit(1_000);
+            let minimum = minimum_size(&options).unwrap();
+            assert_exact_sizes(&options, minimum);
+            assert_exact_sizes(&options, minimum + 1_000 - 32);
+        }
+    }
+
+    #[test]
+    fn every_size_with_several_form_fields() {
+        let options = Options::default().padding(PaddingStrategy::FormFields).form_fields(3);
+        assert_exact_sizes(&options, minimum_size(&options).unwrap());
+    }
+
+    #[test]
+    fn every_size_with_several_annotations_per_page() {
+        let options = Options::default().padding(PaddingStrategy::Annotations).annotations_per_page(3).page_count(2);
+        assert_exact_sizes(&options, minimum_size(&options).unwrap());
+    }
+
+    #[test]
+    fn every_size_with_page_content_sizes() {
+        let options = Options::default()
+            .padding(PaddingStrategy::FormFields)
+            .form_fields(3)
+            .page_count(2)
+            .page_content_sizes([2_000, 300]);
+        assert_exact_sizes(&options, minimum_size(&options).unwrap());
+    }
+}
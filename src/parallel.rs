@@ -0,0 +1,29 @@
+//! Generating a whole corpus of documents on all cores, available with the `rayon` feature.
+
+use rayon::prelude::*;
+
+use crate::{Error, Generator, Options};
+
+/// Generates one serialized document for each of `sizes`, in the same order. Every worker thread
+/// reuses the buffers of a single [`Generator`].
+pub fn generate_many(sizes: &[u64]) -> impl IndexedParallelIterator<Item = Result<Vec<u8>, Error>> + '_ {
+    generate_many_with_options(sizes, &Options::default())
+}
+
+/// Generates documents like [`generate_many`], applying `options` to all of them.
+pub fn generate_many_with_options<'a>(
+    sizes: &'a [u64],
+    options: &Options,
+) -> impl IndexedParallelIterator<Item = Result<Vec<u8>, Error>> + 'a {
+    let options = options.clone();
+    sizes.par_iter().map_init(
+        move || Generator::new(options.clone()),
+        |generator, &size| {
+            let size = usize::try_from(size)
+                .map_err(|_| Error::InvalidOption(format!("A file of {size} bytes does not fit into memory.")))?;
+            let mut bytes = Vec::new();
+            generator.generate_into(size, &mut bytes)?;
+            Ok(bytes)
+        },
+    )
+}
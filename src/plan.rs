@@ -0,0 +1,145 @@
+//! A breakdown of how the bytes of a document are spent, computed without writing it anywhere.
+
+use std::fmt::{Display, Formatter};
+use std::io::Write;
+
+use lopdf::Document;
+
+use crate::generator::Scratch;
+use crate::sizing::Layout;
+use crate::{build_document, minimum_size, padding_objects, serialized_len, smallest_size, solve_layout};
+use crate::{signature, Error, Options};
+
+/// How a document of [`file_size`](Self::file_size) bytes is made up. The sizes of
+/// [`structure`](Self::structure), [`padding`](Self::padding), [`xref`](Self::xref) and
+/// [`slack`](Self::slack) add up to the size of the file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GenerationPlan {
+    pub file_size: usize,
+    /// Size of the smallest document which can be generated with the same options.
+    pub minimum_size: usize,
+    /// The header and the objects, apart from the data of the padding: object headers,
+    /// dictionaries and the keywords around streams.
+    pub structure: usize,
+    /// The data of the content streams and images and the strings of the form fields and
+    /// annotations which hold the padding, including the operators around the filler.
+    pub padding: usize,
+    /// The cross reference section, the trailer and the `startxref` offset.
+    pub xref: usize,
+    /// The slack added to the trailer, including its key, to reach a size the filler skips.
+    pub slack: usize,
+    /// Bytes the structure and the cross reference section grew by compared to the smallest
+    /// document, because printed numbers like the `/Length` of streams and the cross reference
+    /// offsets got wider, or the filler is split across further content streams.
+    pub digit_adjustment: usize,
+}
+
+impl Display for GenerationPlan {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "{} bytes, at least {} bytes with these options", self.file_size, self.minimum_size)?;
+        writeln!(f, "  structure         {:>12} bytes", self.structure)?;
+        writeln!(f, "  padding           {:>12} bytes", self.padding)?;
+        writeln!(f, "  cross references  {:>12} bytes", self.xref)?;
+        writeln!(f, "  trailer slack     {:>12} bytes", self.slack)?;
+        write!(f, "  digit adjustment  {:>12} bytes", self.digit_adjustment)
+    }
+}
+
+/// Plans a document of `file_size_bytes` bytes with the default options.
+pub fn plan(file_size_bytes: usize) -> Result<GenerationPlan, Error> {
+    plan_with_options(file_size_bytes, &Options::default())
+}
+
+/// Plans the document [`generate_pdf_with_options`](crate::generate_pdf_with_options) generates.
+/// The layout is solved like for generating it, but the document is only measured, the filler is
+/// never written. Documents beyond the 32 bit offsets of lopdf are planned as the
+/// [native writer](crate::native) writes them, if it supports `options`.
+pub fn plan_with_options(file_size_bytes: usize, options: &Options) -> Result<GenerationPlan, Error> {
+    #[cfg(feature = "native-writer")]
+    if crate::validate_lopdf_size(file_size_bytes).is_err() && crate::native::supports(options) {
+        return Ok(crate::native::Solved::new(file_size_bytes, options)?.plan(options));
+    }
+
+    let mut scratch = Scratch::default();
+    let (id, layout) = solve_layout(file_size_bytes, options, &mut scratch)?;
+    let (padding, sink) = scratch.measure(|scratch| {
+        let mut doc = build_document(options, id.as_deref(), layout, scratch)?;
+        signature::set_byte_range(&mut doc, file_size_bytes)?;
+        let padding = padding_len(&doc)?;
+        let mut sink = TailSink::default();
+        doc.save_to(&mut sink)?;
+        scratch.reclaim(doc);
+        Ok::<_, Error>((padding, sink))
+    })?;
+    let xref_start = sink.startxref().expect("lopdf ends the document with the startxref offset");
+
+    // the smallest documents are enough to measure the key of the slack, which does not move any
+    // number, and the padding which the smallest document has regardless
+    let mut smallest = |slack| build_document(options, id.as_deref(), Layout { filler: 0, slack }, &mut scratch);
+    let slack = match layout.slack {
+        Some(slack) => slack + serialized_len(&mut smallest(Some(0))?)? - serialized_len(&mut smallest(None)?)?,
+        None => 0,
+    };
+    let minimum_overhead = smallest_size(options)? - padding_len(&smallest(None)?)?;
+    let overhead = sink.bytes_written - padding - slack;
+
+    Ok(GenerationPlan {
+        file_size: sink.bytes_written,
+        minimum_size: minimum_size(options)?,
+        structure: xref_start - padding,
+        padding,
+        xref: sink.bytes_written - xref_start - slack,
+        slack,
+        digit_adjustment: overhead - minimum_overhead,
+    })
+}
+
+/// Sums up the data of the objects which hold the padding.
+fn padding_len(doc: &Document) -> Result<usize, Error> {
+    let padding = padding_objects(doc)?;
+    let mut len = 0;
+    for id in padding.content_ids.iter().chain(&padding.xobject_ids) {
+        len += doc.get_object(*id)?.as_stream()?.content.len();
+    }
+    for id in &padding.field_ids {
+        len += doc.get_dictionary(*id)?.get(b"V")?.as_str()?.len();
+    }
+    for id in &padding.annotation_ids {
+        len += doc.get_dictionary(*id)?.get(b"Contents")?.as_str()?.len();
+    }
+    Ok(len)
+}
+
+/// A writer discarding everything but the amount of bytes written to it and the last few of them,
+/// which hold the `startxref` offset.
+#[derive(Default)]
+struct TailSink {
+    bytes_written: usize,
+    tail: Vec<u8>,
+}
+
+impl TailSink {
+    /// Enough for `startxref`, an offset of any size and `%%EOF`.
+    const TAIL_LEN: usize = 64;
+
+    fn startxref(&self) -> Option<usize> {
+        const KEYWORD: &[u8] = b"startxref\n";
+        let start = self.tail.windows(KEYWORD.len()).rposition(|window| window == KEYWORD)? + KEYWORD.len();
+        let digits = self.tail[start..].iter().take_while(|byte| byte.is_ascii_digit()).count();
+        std::str::from_utf8(&self.tail[start..start + digits]).ok()?.parse().ok()
+    }
+}
+
+impl Write for TailSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.bytes_written += buf.len();
+        self.tail.extend_from_slice(&buf[buf.len().saturating_sub(Self::TAIL_LEN)..]);
+        let excess = self.tail.len().saturating_sub(Self::TAIL_LEN);
+        self.tail.drain(..excess);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
@@ -0,0 +1,44 @@
+//! Reporting how much of a document has been written, for files large enough to take a while.
+
+use std::io::Write;
+
+/// Amount of bytes passed to the inner writer at once. lopdf writes each stream in a single call,
+/// so larger writes are split up to report progress in between.
+const CHUNK_SIZE: usize = 1 << 20;
+
+/// A writer calling `callback` with the amount of bytes written so far and the expected total
+/// after every chunk written to `inner`.
+pub struct ProgressWriter<W, F> {
+    inner: W,
+    written: usize,
+    total: usize,
+    callback: F,
+}
+
+impl<W: Write, F: FnMut(usize, usize)> ProgressWriter<W, F> {
+    pub fn new(inner: W, total: usize, callback: F) -> Self {
+        ProgressWriter { inner, written: 0, total, callback }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.inner
+    }
+
+    /// Calls the callback with the progress so far, without writing anything.
+    pub fn report(&mut self) {
+        (self.callback)(self.written, self.total);
+    }
+}
+
+impl<W: Write, F: FnMut(usize, usize)> Write for ProgressWriter<W, F> {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let written = self.inner.write(&buf[..buf.len().min(CHUNK_SIZE)])?;
+        self.written += written;
+        (self.callback)(self.written, self.total);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.inner.flush()
+    }
+}
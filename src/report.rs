@@ -0,0 +1,139 @@
+//! Where the bytes of a generated file went, for tools working on particular byte ranges of it, e.g.
+//! to corrupt exactly the padding.
+
+use std::ops::Range;
+
+use lopdf::{Document, ObjectId};
+
+use crate::verify::startxref;
+use crate::{generate_pdf_with_options, padding_objects, save_to_vec, Error, Options};
+
+/// The byte ranges of a file generated by [`generate_pdf_bytes_with_report`]. Every range covers
+/// the data of an object holding the padding, ordered like the objects in the file: the data of a
+/// stream between the `stream` and `endstream` keywords, or the bytes of a string between its
+/// parentheses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SizeReport {
+    pub file_size: usize,
+    /// Everything but the data of the padding: the header, object headers, dictionaries, the cross
+    /// reference section and the trailer.
+    pub structure: usize,
+    /// The data of all ranges below, including the operators around the filler.
+    pub padding: usize,
+    /// The content streams of every page, page by page.
+    pub content_streams: Vec<Range<usize>>,
+    /// The images drawn by the content streams.
+    pub images: Vec<Range<usize>>,
+    /// The values of the form fields.
+    pub field_values: Vec<Range<usize>>,
+    /// The contents of the annotations.
+    pub annotation_contents: Vec<Range<usize>>,
+    /// Offset of the cross reference section, as given after `startxref`.
+    pub xref_offset: usize,
+}
+
+/// Generates a document like [`generate_pdf_bytes`](crate::generate_pdf_bytes) and reports where
+/// its bytes went.
+pub fn generate_pdf_bytes_with_report(
+    file_size_bytes: usize,
+    options: &Options,
+) -> Result<(Vec<u8>, SizeReport), Error> {
+    let mut doc = generate_pdf_with_options(file_size_bytes, options)?;
+    let padding = padding_objects(&doc)?;
+    let groups = [&padding.content_ids, &padding.xobject_ids, &padding.field_ids, &padding.annotation_ids];
+
+    let mut objects = Vec::new();
+    for (group, ids) in groups.into_iter().enumerate() {
+        for (index, id) in ids.iter().enumerate() {
+            objects.push((*id, group, index, data_len(&doc, *id, group)?));
+        }
+    }
+    // lopdf writes the objects in the order of their IDs
+    objects.sort_by_key(|(id, ..)| *id);
+    let bytes = save_to_vec(&mut doc)?;
+
+    let mut ranges = groups.map(|ids| vec![0..0; ids.len()]);
+    let mut position = 0;
+    for (id, group, index, len) in objects {
+        let range = locate(&bytes, position, id, group, len).expect("lopdf writes the padding as it is");
+        position = range.end;
+        ranges[group][index] = range;
+    }
+    let file_size = bytes.len();
+    let padding = ranges.iter().flatten().map(|range| range.len()).sum();
+    let xref_offset = startxref(&bytes).expect("lopdf ends the document with the startxref offset");
+    let [content_streams, images, field_values, annotation_contents] = ranges;
+    let report = SizeReport {
+        file_size,
+        structure: file_size - padding,
+        padding,
+        content_streams,
+        images,
+        field_values,
+        annotation_contents,
+        xref_offset,
+    };
+    Ok((bytes, report))
+}
+
+/// The bytes preceding and following the data of the padding objects in each group of
+/// [`PaddingObjects`](crate::checksum::PaddingObjects): the keywords around stream data or the key
+/// and the parentheses of a string.
+const FRAMING: [(&[u8], &[u8]); 4] =
+    [(b"stream\n", b"\nendstream"), (b"stream\n", b"\nendstream"), (b"/V(", b")"), (b"/Contents(", b")")];
+
+/// Returns the length of the data of a padding object, `group` indexes the groups of
+/// [`PaddingObjects`](crate::checksum::PaddingObjects).
+fn data_len(doc: &Document, id: ObjectId, group: usize) -> Result<usize, Error> {
+    let data = match group {
+        0 | 1 => &doc.get_object(id)?.as_stream()?.content,
+        2 => doc.get_dictionary(id)?.get(b"V")?.as_str()?,
+        _ => doc.get_dictionary(id)?.get(b"Contents")?.as_str()?,
+    };
+    Ok(data.len())
+}
+
+/// Finds the `len` bytes of data of the padding object `id` in the serialized document, looking
+/// for its object header from `from` on and then for the framing of its data. lopdf writes strings
+/// without escaping when there is nothing to escape, which the padding never has.
+///
+/// Neither the padding nor any object between two padding objects contains an object header, and
+/// the dictionaries of padding objects contain their framing only once.
+fn locate(bytes: &[u8], from: usize, id: ObjectId, group: usize, len: usize) -> Option<Range<usize>> {
+    let (open, close) = FRAMING[group];
+    let header = format!("\n{} {} obj\n", id.0, id.1);
+    let object = from + find(&bytes[from..], header.as_bytes())? + header.len();
+    let start = object + find(&bytes[object..], open)? + open.len();
+    let end = start + len;
+    (bytes.get(end..end + close.len())? == close).then_some(start..end)
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack.windows(needle.len()).position(|window| window == needle)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{generate_pdf_bytes_with_report, Options, PaddingStrategy};
+
+    #[test]
+    fn ranges_cover_the_padding() {
+        for padding in PaddingStrategy::ALL {
+            let options =
+                Options::default().padding(padding).caption("(5 0 obj)").form_fields(3).annotations_per_page(2);
+            let (bytes, report) = generate_pdf_bytes_with_report(100_000, &options).unwrap();
+            assert_eq!(report.file_size, bytes.len());
+            assert_eq!(report.structure + report.padding, bytes.len());
+            for range in report.field_values.iter().chain(&report.annotation_contents) {
+                assert!(!range.is_empty() && bytes[range.clone()].iter().all(|byte| *byte == b'4'), "{padding:?}");
+                assert_eq!((bytes[range.start - 1], bytes[range.end]), (b'(', b')'));
+            }
+            for range in report.content_streams.iter().chain(&report.images) {
+                assert!(bytes[..range.start].ends_with(b"stream\n") && bytes[range.end..].starts_with(b"\nendstream"));
+            }
+            // a cross reference table or the header of a cross reference stream
+            let xref = &bytes[report.xref_offset..];
+            assert!(xref.starts_with(b"xref\n") || xref[..16].windows(6).any(|window| window == b" 0 obj"));
+        }
+    }
+}
@@ -0,0 +1,162 @@
+//! Empty signature fields which external tools can sign in place.
+//!
+//! A signature covers the whole file except for its own `/Contents`, a hexadecimal string of NUL
+//! bytes reserved for the signature. The `/ByteRange` lists the ranges of the file before and after
+//! that string and is printed after it, so its digits never move the string. The second range ends
+//! at the requested size, which is why the ranges are filled in for every candidate document the
+//! size calculation measures.
+
+use std::io::Write;
+
+use lopdf::{dictionary, Dictionary, Document, Object, ObjectId, StringFormat};
+
+use crate::Error;
+
+/// Name of the signature field.
+const FIELD_NAME: &str = "Signature1";
+/// The form contains signatures and must only be appended to, so they stay valid.
+const SIGNATURES_EXIST_APPEND_ONLY: i64 = 3;
+
+/// The signature dictionary with `len` bytes reserved for the signature. `/Contents` precedes
+/// `/ByteRange`, see the module documentation.
+pub(crate) fn placeholder(len: usize) -> Dictionary {
+    dictionary! {
+        "Type" => "Sig",
+        "Filter" => "Adobe.PPKLite",
+        "SubFilter" => "adbe.pkcs7.detached",
+        "Contents" => Object::String(vec![0; len], StringFormat::Hexadecimal),
+        "ByteRange" => vec![0.into(), 0.into(), 0.into(), 0.into()],
+    }
+}
+
+/// An invisible signature field with the value `signature_id`.
+pub(crate) fn field(signature_id: ObjectId) -> Dictionary {
+    dictionary! {
+        "Type" => "Annot",
+        "Subtype" => "Widget",
+        "FT" => "Sig",
+        "T" => Object::string_literal(FIELD_NAME),
+        "Rect" => vec![0.into(), 0.into(), 0.into(), 0.into()],
+        // printed, if a signing tool adds an appearance
+        "F" => 4,
+        "V" => signature_id,
+    }
+}
+
+/// Adds the entries the interactive form needs once it contains a signature field.
+pub(crate) fn flag_form(form: &mut Dictionary) {
+    form.set("SigFlags", SIGNATURES_EXIST_APPEND_ONLY);
+}
+
+/// Fills in the `/ByteRange` of the signature of `doc`, if there is one, for a file which is
+/// `file_size_bytes` bytes large once saved.
+pub(crate) fn set_byte_range(doc: &mut Document, file_size_bytes: usize) -> Result<(), Error> {
+    let Some(signature_id) = signature_id(doc)? else {
+        return Ok(());
+    };
+
+    // lopdf writes the header followed by the objects in the order of their IDs, so the objects up
+    // to the signature are enough to locate its contents
+    let mut prefix = Document::with_version(doc.version.clone());
+    prefix.objects = doc.objects.range(..=signature_id).map(|(id, object)| (*id, object.clone())).collect();
+    let mut sink = MarkerSink::new(b"/Contents<");
+    prefix.save_to(&mut sink)?;
+    let start = sink.found.expect("the signature dictionary is written with its contents") + b"/Contents".len();
+
+    let signature = doc.get_dictionary_mut(signature_id)?;
+    let end = start + 2 + 2 * signature.get(b"Contents")?.as_str()?.len();
+    let byte_range = [0, start, end, file_size_bytes.saturating_sub(end)];
+    signature.set("ByteRange", byte_range.iter().map(|&offset| (offset as i64).into()).collect::<Vec<Object>>());
+    Ok(())
+}
+
+/// Looks up the value of the signature field in the interactive form of `doc`.
+fn signature_id(doc: &Document) -> Result<Option<ObjectId>, Error> {
+    let Ok(form) = doc.catalog()?.get(b"AcroForm") else {
+        return Ok(None);
+    };
+    for field in form.as_dict()?.get(b"Fields")?.as_array()? {
+        let field = doc.get_dictionary(field.as_reference()?)?;
+        if field.get(b"FT")?.as_name()? == b"Sig" {
+            return Ok(Some(field.get(b"V")?.as_reference()?));
+        }
+    }
+    Ok(None)
+}
+
+/// A writer discarding everything but the offset of the first occurrence of `marker`.
+struct MarkerSink {
+    marker: &'static [u8],
+    /// The end of the data written so far, which may contain the start of the marker.
+    recent: Vec<u8>,
+    bytes_written: usize,
+    found: Option<usize>,
+}
+
+impl MarkerSink {
+    fn new(marker: &'static [u8]) -> Self {
+        MarkerSink { marker, recent: Vec::new(), bytes_written: 0, found: None }
+    }
+}
+
+impl Write for MarkerSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        if self.found.is_none() {
+            let start = self.bytes_written - self.recent.len();
+            self.recent.extend_from_slice(buf);
+            match self.recent.windows(self.marker.len()).position(|window| window == self.marker) {
+                Some(position) => self.found = Some(start + position),
+                None => {
+                    let searched = self.recent.len().saturating_sub(self.marker.len() - 1);
+                    self.recent.drain(..searched);
+                }
+            }
+        }
+        self.bytes_written += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::verify::{verify_pdf_bytes, SizeTarget};
+    use crate::{generate_pdf_bytes, minimum_size, Options, PaddingStrategy};
+
+    /// Parses the `/ByteRange` array as lopdf prints it.
+    fn byte_range(bytes: &[u8]) -> Vec<usize> {
+        const KEY: &[u8] = b"/ByteRange[";
+        let start = bytes.windows(KEY.len()).position(|window| window == KEY).unwrap() + KEY.len();
+        let len = bytes[start..].iter().position(|&byte| byte == b']').unwrap();
+        let array = std::str::from_utf8(&bytes[start..start + len]).unwrap();
+        array.split_whitespace().map(|offset| offset.parse().unwrap()).collect()
+    }
+
+    #[test]
+    fn byte_range_covers_everything_but_the_contents() {
+        for padding in [PaddingStrategy::Text, PaddingStrategy::HexText, PaddingStrategy::FormFields] {
+            let reserved = 100;
+            let options = Options::default().padding(padding).signature_placeholder(reserved);
+            let minimum = minimum_size(&options).unwrap();
+            // the byte range gains digits along with the size
+            for size in (minimum..minimum + 32).chain(10_000 - 16..10_000 + 16).chain(100_000 - 16..100_000 + 16) {
+                let bytes = generate_pdf_bytes(size, &options).unwrap();
+                assert!(verify_pdf_bytes(&bytes, SizeTarget::Exact(size)).is_valid(), "{padding:?} {size}");
+
+                let [first_start, first_len, second_start, second_len] = byte_range(&bytes)[..] else {
+                    panic!("{padding:?} {size}: the byte range does not hold two ranges");
+                };
+                assert_eq!(first_start, 0);
+                assert_eq!(second_start + second_len, size, "{padding:?}: the byte range ends early");
+                let contents = &bytes[first_len..second_start];
+                assert!(bytes[..first_len].ends_with(b"/Contents"), "{padding:?} {size}");
+                assert_eq!(contents.len(), 2 + 2 * reserved);
+                assert!(contents.starts_with(b"<") && contents.ends_with(b">"), "{padding:?} {size}");
+                assert!(contents[1..contents.len() - 1].iter().all(|&digit| digit == b'0'), "{padding:?} {size}");
+            }
+        }
+    }
+}
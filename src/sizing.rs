@@ -0,0 +1,230 @@
+//! Finding the amount of filler which makes a document exactly as large as requested.
+//!
+//! The overhead of a document is not constant, because some of the numbers lopdf prints depend on
+//! the amount of filler:
+//! 1. the `/Length` of every stream containing filler
+//! 2. the offset of the cross reference section printed after `startxref`, which follows the
+//!    filler
+//!
+//! Instead of deriving the overhead from constants measured by hand, the document is serialized
+//! into a [`CountingSink`] and the filler length is corrected until the sizes match.
+//! Every additional filler byte grows the file by at least one byte, unless a
+//! [run length encoded](crate::StreamFilter::RunLength) stream compresses it, but growing a printed
+//! number by a digit makes the file skip sizes. These sizes can only be hit by adding a few bytes of slack
+//! to the trailer, which is written after the cross reference offset has been determined.
+
+use std::io::Write;
+
+use lopdf::{Dictionary, Object, StringFormat};
+
+use crate::Error;
+
+/// Trailer key holding the slack, ignored by readers.
+const SLACK_KEY: &str = "Padding";
+
+/// The amount of filler and trailer slack which produces the requested file size.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) struct Layout {
+    pub filler: usize,
+    pub slack: Option<usize>,
+}
+
+/// Solves for the [`Layout`] of a `target` bytes large file.
+///
+/// `measure` has to return the serialized size of the document built from a given layout.
+#[cfg_attr(
+    feature = "tracing",
+    tracing::instrument(name = "overhead", level = "debug", skip_all, fields(target = target, minimum), ret)
+)]
+pub(crate) fn solve<M>(target: usize, mut measure: M) -> Result<Layout, Error>
+where
+    M: FnMut(Layout) -> Result<usize, Error>,
+{
+    let minimum = measure(Layout { filler: 0, slack: None })?;
+    #[cfg(feature = "tracing")]
+    tracing::Span::current().record("minimum", minimum);
+    if target < minimum {
+        return Err(Error::FileTooSmall { requested: target, minimum });
+    }
+
+    let (filler, size) = fit(target, |filler| measure(Layout { filler, slack: None }))?;
+    if size == target {
+        return Ok(Layout { filler, slack: None });
+    }
+
+    // the slack does not influence any printed number, so its overhead is constant
+    let slack_overhead = measure(Layout { filler: 0, slack: Some(0) })? - minimum;
+    let budget = target
+        .checked_sub(slack_overhead)
+        .filter(|budget| *budget >= minimum)
+        .ok_or(Error::FileTooSmall { requested: target, minimum: minimum + slack_overhead })?;
+    let (filler, size) = fit(budget, |filler| measure(Layout { filler, slack: None }))?;
+
+    Ok(Layout { filler, slack: Some(budget - size) })
+}
+
+/// Returns the smallest target from which on [`solve`] finds a layout for every size, given the size
+/// of the `smallest` document. `measure` also gets the target, which the document may print.
+///
+/// Every size of at least the smallest document plus the overhead of the slack can be reached.
+/// Smaller sizes can only be reached by filler alone, which skips some of them when a filler byte
+/// takes several bytes, or another digit of a printed number comes with the filler byte.
+pub(crate) fn smallest_target<M>(smallest: usize, mut measure: M) -> Result<usize, Error>
+where
+    M: FnMut(usize, Layout) -> Result<usize, Error>,
+{
+    let slack_overhead = measure(smallest, Layout { filler: 0, slack: Some(0) })? - smallest;
+    let mut target = smallest + slack_overhead;
+    while target > smallest {
+        match solve(target - 1, |layout| measure(target - 1, layout)) {
+            Ok(_) => target -= 1,
+            Err(Error::FileTooSmall { .. }) => break,
+            Err(error) => return Err(error),
+        }
+    }
+    Ok(target)
+}
+
+/// Finds the largest filler length whose document does not exceed `budget` bytes and returns it
+/// together with the size of that document.
+fn fit<M>(budget: usize, mut measure: M) -> Result<(usize, usize), Error>
+where
+    M: FnMut(usize) -> Result<usize, Error>,
+{
+    // every filler byte adds at least one byte unless it is compressed, so this is usually an upper bound
+    let mut filler = budget - measure(0)?;
+    let mut size = measure(filler)?;
+    while size > budget {
+        filler = filler.saturating_sub(size - budget);
+        size = measure(filler)?;
+    }
+
+    // removing filler may have shrunk a printed number or dropped a content stream, leaving room
+    // for larger candidates, though none beyond the upper bound from above. Compressed filler takes
+    // less than a byte per byte, so the step doubles until a candidate is too large.
+    let (mut low, mut step) = (filler, budget - size);
+    let mut high = loop {
+        if step == 0 {
+            return Ok((low, size));
+        }
+        let candidate = low + step;
+        let candidate_size = measure(candidate)?;
+        if candidate_size > budget {
+            break candidate - 1;
+        }
+        (low, size) = (candidate, candidate_size);
+        step = if size == budget { 0 } else { 2 * step };
+    };
+    while low < high {
+        let candidate = low + (high - low).div_ceil(2);
+        let candidate_size = measure(candidate)?;
+        if candidate_size <= budget {
+            low = candidate;
+            size = candidate_size;
+        } else {
+            high = candidate - 1;
+        }
+    }
+
+    Ok((low, size))
+}
+
+/// Adds the slack of `layout` to a trailer dictionary.
+pub(crate) fn apply_slack(trailer: &mut Dictionary, layout: Layout) {
+    if let Some(slack) = layout.slack {
+        trailer.set(SLACK_KEY, Object::String(vec![b' '; slack], StringFormat::Literal));
+    }
+}
+
+/// A writer discarding everything but the amount of bytes written to it.
+#[derive(Debug, Default)]
+pub(crate) struct CountingSink {
+    pub bytes_written: usize,
+}
+
+impl Write for CountingSink {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.bytes_written += buf.len();
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use crate::verify::{verify_pdf_bytes, SizeTarget};
+    use crate::{generate_pdf_bytes, minimum_size, CjkFont, Error, Options, PaddingStrategy, StreamFilter};
+
+    /// Generates every size from `from` on for a while, each of which has to come out exactly.
+    pub(crate) fn assert_exact_sizes(options: &Options, from: usize) {
+        for size in from..from + 64 {
+            let bytes = generate_pdf_bytes(size, options).unwrap_or_else(|error| panic!("{size} bytes: {error}"));
+            assert_eq!(bytes.len(), size);
+            assert!(verify_pdf_bytes(&bytes, SizeTarget::Exact(size)).is_valid(), "{size} bytes");
+        }
+    }
+
+    #[test]
+    fn every_size_from_the_minimum() {
+        for padding in PaddingStrategy::ALL {
+            let options = Options::default().padding(padding);
+            assert_exact_sizes(&options, minimum_size(&options).unwrap());
+        }
+    }
+
+    #[test]
+    fn every_size_with_stream_filters() {
+        use StreamFilter::{Ascii85, AsciiHex, RunLength};
+        for filters in [&[RunLength][..], &[Ascii85], &[AsciiHex], &[Ascii85, AsciiHex], &[RunLength, Ascii85]] {
+            for padding in [PaddingStrategy::Text, PaddingStrategy::HexText] {
+                let options = Options::default().padding(padding).stream_filters(filters.iter().copied());
+                assert_exact_sizes(&options, minimum_size(&options).unwrap());
+            }
+        }
+    }
+
+    #[test]
+    fn every_size_with_a_unicode_caption() {
+        for padding in [PaddingStrategy::Text, PaddingStrategy::HexText] {
+            let options =
+                Options::default().padding(padding).caption("生成されたPDF, ملف").unicode_caption(CjkFont::Japanese);
+            assert_exact_sizes(&options, minimum_size(&options).unwrap());
+        }
+    }
+
+    #[test]
+    fn every_size_around_digit_boundaries() {
+        for padding in [PaddingStrategy::Text, PaddingStrategy::HexText] {
+            let options = Options::default().padding(padding);
+            for boundary in [1_000, 10_000, 100_000] {
+                assert_exact_sizes(&options, boundary - 32);
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn rejects_sizes_beyond_the_offsets_of_lopdf() {
+        let size = u32::MAX as usize + 1;
+        assert!(matches!(generate_pdf_bytes(size, &Options::default()), Err(Error::InvalidOption(_))));
+        let incremental = crate::incremental::generate_incremental_pdf_with_size(size, 1);
+        assert!(matches!(incremental, Err(Error::InvalidOption(_))));
+    }
+
+    #[test]
+    fn too_small_reports_the_minimum() {
+        let options = Options::default().padding(PaddingStrategy::HexText);
+        let minimum = minimum_size(&options).unwrap();
+        for size in [0, minimum - 1] {
+            match generate_pdf_bytes(size, &options) {
+                Err(Error::FileTooSmall { requested, minimum: reported }) => {
+                    assert_eq!((requested, reported), (size, minimum));
+                }
+                result => panic!("{size} bytes: {:?}", result.map(|bytes| bytes.len())),
+            }
+        }
+    }
+}
@@ -0,0 +1,207 @@
+//! Writing documents as sparse files, so the filler of huge documents costs neither disk space nor
+//! write calls.
+//!
+//! Only blocks of NUL bytes can be left out of a file, which is why this requires
+//! [`Options::zero_filler`]. Every block of the document consisting of NUL bytes only is skipped
+//! by seeking past it, the filesystem then reads it back as zeros. Whether the hole actually saves
+//! disk space depends on the filesystem.
+//!
+//! [`save_sparse_pdf`] writes documents with the native writer if the `native-writer` feature is
+//! enabled and the writer supports the options, which seeks past the filler without writing it, so
+//! documents of any size are written in constant memory and time. Otherwise the document is built
+//! with lopdf and held in memory while it is written, and may not exceed 4 GiB.
+
+use std::fs::File;
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use crate::{generate_pdf_with_options, Error, Options};
+
+/// Granularity of the holes, the block size of most filesystems.
+const BLOCK_SIZE: usize = 4096;
+
+/// A writer leaving holes in a newly created file wherever a whole block of NUL bytes is written.
+/// [`SparseWriter::finish`] has to be called once everything has been written, otherwise the last
+/// block and a hole at the end of the file are lost.
+pub struct SparseWriter {
+    file: BufWriter<File>,
+    /// The start of the current block, collected until it is complete.
+    block: Vec<u8>,
+    position: u64,
+    hole: u64,
+}
+
+impl SparseWriter {
+    /// Creates the file at `path`, truncating it if it exists.
+    pub fn create(path: impl AsRef<Path>) -> std::io::Result<Self> {
+        Ok(SparseWriter {
+            file: BufWriter::new(File::create(path)?),
+            block: Vec::with_capacity(BLOCK_SIZE),
+            position: 0,
+            hole: 0,
+        })
+    }
+
+    /// Writes the incomplete last block, extends the file over the final hole and returns it.
+    pub fn finish(mut self) -> std::io::Result<File> {
+        if !self.block.is_empty() {
+            self.skip_hole()?;
+            self.file.write_all(&self.block)?;
+        }
+        self.file.flush()?;
+        let file = self.file.into_inner().map_err(|error| error.into_error())?;
+        file.set_len(self.position)?;
+        Ok(file)
+    }
+
+    /// Writes `len` NUL bytes without looking at any of them, the whole blocks among them are left
+    /// out of the file.
+    pub fn write_zeros(&mut self, len: usize) -> std::io::Result<()> {
+        const ZEROS: [u8; BLOCK_SIZE] = [0; BLOCK_SIZE];
+        // the current block is completed first, then the whole blocks are added to the hole
+        let head = len.min((BLOCK_SIZE - self.block.len()) % BLOCK_SIZE);
+        self.write_all(&ZEROS[..head])?;
+        let blocks = (len - head) / BLOCK_SIZE * BLOCK_SIZE;
+        self.hole += blocks as u64;
+        self.position += blocks as u64;
+        self.write_all(&ZEROS[..len - head - blocks])
+    }
+
+    fn write_block(&mut self, block: &[u8]) -> std::io::Result<()> {
+        if block.iter().all(|byte| *byte == 0) {
+            self.hole += block.len() as u64;
+        } else {
+            self.skip_hole()?;
+            self.file.write_all(block)?;
+        }
+        Ok(())
+    }
+
+    fn skip_hole(&mut self) -> std::io::Result<()> {
+        if self.hole > 0 {
+            self.file.seek(SeekFrom::Current(self.hole as i64))?;
+            self.hole = 0;
+        }
+        Ok(())
+    }
+}
+
+impl Write for SparseWriter {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        let mut rest = buf;
+        while !rest.is_empty() {
+            // holes can only cover whole blocks of the file, so the data is split at block boundaries
+            let (head, tail) = rest.split_at(rest.len().min(BLOCK_SIZE - self.block.len()));
+            if head.len() == BLOCK_SIZE {
+                self.write_block(head)?;
+            } else {
+                self.block.extend_from_slice(head);
+                if self.block.len() == BLOCK_SIZE {
+                    let block = std::mem::take(&mut self.block);
+                    self.write_block(&block)?;
+                    self.block = block;
+                    self.block.clear();
+                }
+            }
+            self.position += head.len() as u64;
+            rest = tail;
+        }
+        Ok(buf.len())
+    }
+
+    /// Flushes everything but the incomplete current block.
+    fn flush(&mut self) -> std::io::Result<()> {
+        self.file.flush()
+    }
+}
+
+/// Generates a document with NUL filler like [`generate_pdf_with_options`] and saves it as a sparse
+/// file at `path`. The file is only created once the document could be generated, so an existing
+/// file is left as it is if that fails.
+pub fn save_sparse_pdf(file_size_bytes: usize, options: &Options, path: impl AsRef<Path>) -> Result<(), Error> {
+    let options = options.clone().zero_filler(true);
+    #[cfg(feature = "native-writer")]
+    if crate::native::supports(&options) {
+        let document = crate::native::Solved::new(file_size_bytes, &options)?;
+        let mut file = SparseWriter::create(path)?;
+        document.write_to(&mut file, |file, len| file.write_zeros(len))?;
+        file.finish()?;
+        return Ok(());
+    }
+    let mut doc = generate_pdf_with_options(file_size_bytes, &options)?;
+    let mut file = SparseWriter::create(path)?;
+    doc.save_to(&mut file)?;
+    file.finish()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Write;
+
+    use super::SparseWriter;
+
+    #[test]
+    fn writes_holes_and_data_like_a_file() {
+        let path = std::env::temp_dir().join(format!("generatePDF-sparse-{}", std::process::id()));
+        let mut expected = Vec::new();
+        let mut file = SparseWriter::create(&path).unwrap();
+        for (data, zeros) in [(&b"start"[..], 3 * 4096 + 7), (&[1; 5000], 4096 - 5000 % 4096), (b"end", 9000)] {
+            file.write_all(data).unwrap();
+            file.write_zeros(zeros).unwrap();
+            expected.extend_from_slice(data);
+            expected.resize(expected.len() + zeros, 0);
+            file.write_all(&[0; 4100]).unwrap();
+            expected.resize(expected.len() + 4100, 0);
+        }
+        file.finish().unwrap();
+        let written = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(written == expected, "the file differs from the data written");
+    }
+
+    #[test]
+    fn keeps_the_file_if_the_document_cannot_be_generated() {
+        let path = std::env::temp_dir().join(format!("generatePDF-sparse-kept-{}.pdf", std::process::id()));
+        std::fs::write(&path, b"previous").unwrap();
+        let result = super::save_sparse_pdf(100, &crate::Options::default(), &path);
+        let kept = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert!(matches!(result, Err(crate::Error::FileTooSmall { .. })));
+        assert_eq!(kept, b"previous");
+    }
+
+    #[test]
+    #[cfg(all(feature = "native-writer", target_pointer_width = "64"))]
+    fn writes_documents_beyond_ten_digit_offsets() {
+        use std::io::{Read, Seek, SeekFrom};
+
+        use crate::Options;
+
+        let path = std::env::temp_dir().join(format!("generatePDF-sparse-large-{}.pdf", std::process::id()));
+        let size = 20_000_000_000;
+        super::save_sparse_pdf(size, &Options::default(), &path).unwrap();
+        let mut file = std::fs::File::open(&path).unwrap();
+        let len = file.metadata().unwrap().len() as usize;
+        let mut head = vec![0; 1024];
+        file.read_exact(&mut head).unwrap();
+        let mut tail = vec![0; 1024];
+        file.seek(SeekFrom::End(-1024)).unwrap();
+        file.read_exact(&mut tail).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(len, size);
+
+        // the cross reference table lists the objects at the start, the stream ends at the table
+        let text = String::from_utf8_lossy(&tail);
+        let (_, startxref) = text.rsplit_once("startxref\n").unwrap();
+        let xref_start: usize = startxref.trim_end_matches("\n%%EOF\n").parse().unwrap();
+        let xref = &text[text.len() - (size - xref_start)..];
+        assert!(text[..text.len() - xref.len()].ends_with(") Tj\nET\nendstream\nendobj\n"));
+        let entries: Vec<&str> = xref.lines().skip(3).take_while(|line| line.trim_end().ends_with('n')).collect();
+        assert_eq!(entries.len(), 6);
+        for (number, entry) in (1..).zip(entries) {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(head[offset..].starts_with(format!("{number} 0 obj\n").as_bytes()), "object {number}");
+        }
+    }
+}
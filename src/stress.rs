@@ -0,0 +1,133 @@
+//! PDF files of an exact size holding deeply nested arrays or dictionaries, used to exercise the
+//! recursion limits and the stack safety of PDF parsers.
+//!
+//! Like the fixtures of [`corruption`](crate::corruption), the nesting is patched into the
+//! serialized bytes: the catalog of a regular document reserves a string of the same length under
+//! the key `/Nested`, which is overwritten once the document is saved. Neither lopdf nor this crate
+//! ever hold the nested objects, so a depth of millions of levels is only limited by the size of the
+//! file. Reading the files back with a recursive parser, including [`verify`](crate::verify), may
+//! overflow the stack, which is the point of them.
+
+use lopdf::{Object, StringFormat};
+
+use crate::{generate_pdf_with_options, save_to_vec, Error, Options};
+
+/// Key of the catalog holding the nested objects.
+pub(crate) const NESTED_KEY: &str = "Nested";
+
+/// The kind of objects nested into each other.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Nesting {
+    /// Arrays holding a single array each, `[[[]]]`, two bytes per level.
+    Arrays,
+    /// Dictionaries holding a single dictionary each under the key `/A`, `<</A<</A<<>>>>>>`, six
+    /// bytes per level.
+    Dictionaries,
+}
+
+impl Nesting {
+    pub const ALL: [Nesting; 2] = [Nesting::Arrays, Nesting::Dictionaries];
+
+    /// Writes `depth` levels of nested objects, at least one.
+    fn write(&self, depth: usize) -> Vec<u8> {
+        let depth = depth.max(1);
+        match self {
+            Nesting::Arrays => [b"[".repeat(depth), b"]".repeat(depth)].concat(),
+            Nesting::Dictionaries => {
+                [b"<</A".repeat(depth - 1), b"<<>>".to_vec(), b">>".repeat(depth - 1)].concat()
+            }
+        }
+    }
+}
+
+/// Generates a PDF file of exactly `file_size_bytes` bytes with `depth` levels of `nesting` in its
+/// catalog.
+pub fn generate_nested_pdf_with_size(file_size_bytes: usize, nesting: Nesting, depth: usize) -> Result<Vec<u8>, Error> {
+    generate_nested_pdf_with_options(file_size_bytes, nesting, depth, &Options::default())
+}
+
+/// Generates a document like [`generate_nested_pdf_with_size`] with the given options. The nested
+/// objects take up part of the size, so the padding shrinks accordingly.
+pub fn generate_nested_pdf_with_options(
+    file_size_bytes: usize,
+    nesting: Nesting,
+    depth: usize,
+    options: &Options,
+) -> Result<Vec<u8>, Error> {
+    let nested = nesting.write(depth);
+    let options = Options { nesting_placeholder: Some(nested.len()), ..options.clone() };
+    let mut bytes = save_to_vec(&mut generate_pdf_with_options(file_size_bytes, &options)?)?;
+
+    let position = find_placeholder(&bytes, nested.len());
+    bytes[position..position + nested.len()].copy_from_slice(&nested);
+    Ok(bytes)
+}
+
+/// A literal string of `len` bytes including its parentheses.
+pub(crate) fn placeholder(len: usize) -> Object {
+    Object::String(vec![b' '; len - 2], StringFormat::Literal)
+}
+
+/// Returns the position of the placeholder of `len` bytes, which directly follows its key.
+fn find_placeholder(bytes: &[u8], len: usize) -> usize {
+    let placeholder = [format!("/{NESTED_KEY}").into_bytes(), b"(".to_vec(), vec![b' '; len - 2], b")".to_vec()];
+    let key_len = placeholder[0].len();
+    let needle = placeholder.concat();
+    bytes
+        .windows(needle.len())
+        .rposition(|window| window == needle)
+        .expect("the catalog always holds the placeholder")
+        + key_len
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::{Document, Object};
+
+    use super::{generate_nested_pdf_with_options, Nesting, NESTED_KEY};
+    use crate::verify::{verify_pdf_bytes, SizeTarget};
+    use crate::{minimum_size, Options};
+
+    #[test]
+    fn every_size_with_the_nesting_patched_in() {
+        for nesting in Nesting::ALL {
+            for depth in [1, 2, 10, 5_000] {
+                let nested = nesting.write(depth);
+                let options = Options { nesting_placeholder: Some(nested.len()), ..Options::default() };
+                let minimum = minimum_size(&options).unwrap();
+                for size in (minimum..minimum + 16).chain(100_000 - 16..100_000 + 16) {
+                    let bytes = generate_nested_pdf_with_options(size, nesting, depth, &Options::default()).unwrap();
+                    assert_eq!(bytes.len(), size, "{nesting:?} {depth}");
+                    let patched = [format!("/{NESTED_KEY}").into_bytes(), nested.clone()].concat();
+                    assert!(bytes.windows(patched.len()).any(|window| window == patched), "{nesting:?} {depth}");
+                    let placeholder = format!("/{NESTED_KEY}(").into_bytes();
+                    let unpatched = bytes.windows(placeholder.len()).any(|window| window == placeholder);
+                    assert!(!unpatched, "{nesting:?} {depth}");
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn shallow_nesting_parses() {
+        for nesting in Nesting::ALL {
+            let bytes = generate_nested_pdf_with_options(10_000, nesting, 10, &Options::default()).unwrap();
+            assert!(verify_pdf_bytes(&bytes, SizeTarget::Exact(10_000)).is_valid(), "{nesting:?}");
+
+            let doc = Document::load_mem(&bytes).unwrap();
+            let mut object = doc.catalog().unwrap().get(NESTED_KEY.as_bytes()).unwrap();
+            let mut depth = 1;
+            loop {
+                object = match object {
+                    Object::Array(array) if array.len() == 1 => &array[0],
+                    Object::Dictionary(dictionary) if dictionary.len() == 1 => dictionary.get(b"A").unwrap(),
+                    Object::Array(empty) if empty.is_empty() => break,
+                    Object::Dictionary(empty) if empty.is_empty() => break,
+                    object => panic!("{nesting:?}: unexpected {object:?}"),
+                };
+                depth += 1;
+            }
+            assert_eq!(depth, 10, "{nesting:?}");
+        }
+    }
+}
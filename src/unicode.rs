@@ -0,0 +1,191 @@
+//! The caption fonts of [`Options::unicode_caption`](crate::Options::unicode_caption): composite
+//! (Type0) fonts using one of the standard CJK fonts which viewers provide without embedding, with
+//! a predefined CMap from UCS-2 to the character collection of that font. The caption is encoded as
+//! UCS-2, the two byte code of every character is its code point in the Basic Multilingual Plane.
+//!
+//! The CMap maps the code points to CIDs of the collection, so viewers draw the glyphs of the
+//! characters. Characters missing from the collection, like Arabic ones, are drawn as the missing
+//! glyph. A `/ToUnicode` CMap maps the codes back to Unicode, which text extraction relies on.
+
+use std::collections::BTreeSet;
+use std::str::FromStr;
+
+use lopdf::{dictionary, Document, Object, ObjectId, Stream};
+
+use crate::Error;
+
+/// The standard CJK font the caption is printed in, which also selects the characters it can show.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CjkFont {
+    /// HeiseiMin-W3, which covers the Adobe-Japan1 collection.
+    Japanese,
+    /// STSong-Light, which covers the Adobe-GB1 collection.
+    SimplifiedChinese,
+    /// MSung-Light, which covers the Adobe-CNS1 collection.
+    TraditionalChinese,
+    /// HYSMyeongJo-Medium, which covers the Adobe-Korea1 collection.
+    Korean,
+}
+
+impl CjkFont {
+    /// The name of the font and the predefined CMap from UCS-2 to its collection.
+    fn names(self) -> (&'static str, &'static str) {
+        match self {
+            CjkFont::Japanese => ("HeiseiMin-W3", "UniJIS-UCS2-H"),
+            CjkFont::SimplifiedChinese => ("STSong-Light", "UniGB-UCS2-H"),
+            CjkFont::TraditionalChinese => ("MSung-Light", "UniCNS-UCS2-H"),
+            CjkFont::Korean => ("HYSMyeongJo-Medium", "UniKS-UCS2-H"),
+        }
+    }
+
+    /// The ordering and the supplement of the character collection.
+    fn collection(self) -> (&'static str, i64) {
+        match self {
+            CjkFont::Japanese => ("Japan1", 2),
+            CjkFont::SimplifiedChinese => ("GB1", 2),
+            CjkFont::TraditionalChinese => ("CNS1", 0),
+            CjkFont::Korean => ("Korea1", 1),
+        }
+    }
+}
+
+/// Parses the name of a font in kebab case, e.g. `simplified-chinese`.
+impl FromStr for CjkFont {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "japanese" => Ok(CjkFont::Japanese),
+            "simplified-chinese" => Ok(CjkFont::SimplifiedChinese),
+            "traditional-chinese" => Ok(CjkFont::TraditionalChinese),
+            "korean" => Ok(CjkFont::Korean),
+            _ => Err(Error::InvalidOption(format!(
+                "Unknown CJK font {s}, expected japanese, simplified-chinese, traditional-chinese or korean."
+            ))),
+        }
+    }
+}
+
+/// Amount of ranges a single `beginbfrange` block may hold.
+const MAX_RANGES: usize = 100;
+
+/// Encodes `text` as UCS-2 in big endian byte order. Characters beyond the Basic Multilingual Plane
+/// have no code and are replaced by U+FFFD.
+pub(crate) fn encode(text: &str) -> Vec<u8> {
+    text.chars()
+        .map(|char| u16::try_from(u32::from(char)).unwrap_or(0xFFFD))
+        .flat_map(u16::to_be_bytes)
+        .collect()
+}
+
+/// Adds `font` for the caption `text` to `doc`.
+pub(crate) fn add_font(doc: &mut Document, text: &str, font: CjkFont) -> ObjectId {
+    let (base_font, cmap) = font.names();
+    let (ordering, supplement) = font.collection();
+    let descriptor_id = doc.add_object(dictionary! {
+        "Type" => "FontDescriptor",
+        "FontName" => base_font,
+        // symbolic, the characters are not a subset of the standard Latin character set
+        "Flags" => 4,
+        "FontBBox" => vec![(-200).into(), (-300).into(), 1200.into(), 1000.into()],
+        "ItalicAngle" => 0,
+        "Ascent" => 880,
+        "Descent" => -120,
+        "CapHeight" => 700,
+        "StemV" => 80,
+    });
+    let descendant_id = doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "CIDFontType0",
+        "BaseFont" => base_font,
+        "CIDSystemInfo" => dictionary! {
+            "Registry" => Object::string_literal("Adobe"),
+            "Ordering" => Object::string_literal(ordering),
+            "Supplement" => supplement,
+        },
+        "FontDescriptor" => descriptor_id,
+        // the ideographs are square, the CMaps map ASCII to the first 95 CIDs, which are about half
+        // as wide
+        "DW" => 1000,
+        "W" => vec![1.into(), 95.into(), 500.into()],
+    });
+    let to_unicode_id = doc.add_object(Stream::new(dictionary! {}, to_unicode(text)));
+    doc.add_object(dictionary! {
+        "Type" => "Font",
+        "Subtype" => "Type0",
+        "BaseFont" => format!("{base_font}-{cmap}"),
+        "Encoding" => cmap,
+        "DescendantFonts" => vec![descendant_id.into()],
+        "ToUnicode" => to_unicode_id,
+    })
+}
+
+/// A CMap mapping every code to the code point of the same value. Ranges may only differ in their
+/// last byte, so there is one for every high byte occurring in `text`.
+fn to_unicode(text: &str) -> Vec<u8> {
+    let high_bytes: BTreeSet<u8> = encode(text).chunks_exact(2).map(|code| code[0]).collect();
+    let high_bytes: Vec<u8> = high_bytes.into_iter().collect();
+
+    let mut ranges = String::new();
+    for block in high_bytes.chunks(MAX_RANGES) {
+        ranges += &format!("{} beginbfrange\n", block.len());
+        for high in block {
+            ranges += &format!("<{high:02X}00> <{high:02X}FF> <{high:02X}00>\n");
+        }
+        ranges += "endbfrange\n";
+    }
+
+    format!(
+        "/CIDInit /ProcSet findresource begin
+12 dict begin
+begincmap
+/CIDSystemInfo << /Registry (Adobe) /Ordering (UCS) /Supplement 0 >> def
+/CMapName /Adobe-Identity-UCS def
+/CMapType 2 def
+1 begincodespacerange
+<0000> <FFFF>
+endcodespacerange
+{ranges}endcmap
+CMapName currentdict /CMap defineresource pop
+end
+end"
+    )
+    .into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::{Document, Object};
+
+    use super::{encode, CjkFont};
+    use crate::{generate_pdf_bytes, padding, Options};
+
+    #[test]
+    fn prints_the_caption_in_the_standard_font() {
+        let caption = "生成されたPDF";
+        for (font, base_font, ordering) in [
+            (CjkFont::Japanese, "HeiseiMin-W3-UniJIS-UCS2-H", "Japan1"),
+            (CjkFont::SimplifiedChinese, "STSong-Light-UniGB-UCS2-H", "GB1"),
+            (CjkFont::TraditionalChinese, "MSung-Light-UniCNS-UCS2-H", "CNS1"),
+            (CjkFont::Korean, "HYSMyeongJo-Medium-UniKS-UCS2-H", "Korea1"),
+        ] {
+            let options = Options::default().caption(caption).unicode_caption(font);
+            let doc = Document::load_mem(&generate_pdf_bytes(20_000, &options).unwrap()).unwrap();
+            let page_id = crate::first_page(&doc).unwrap();
+            let fonts = doc.get_page_fonts(page_id).unwrap();
+            let type0 = fonts[padding::CAPTION_FONT.as_bytes()];
+            assert_eq!(type0.get(b"BaseFont").unwrap().as_name().unwrap(), base_font.as_bytes());
+            let descendants = type0.get(b"DescendantFonts").unwrap().as_array().unwrap();
+            let descendant = doc.get_dictionary(descendants[0].as_reference().unwrap()).unwrap();
+            let system_info = descendant.get(b"CIDSystemInfo").unwrap().as_dict().unwrap();
+            assert_eq!(system_info.get(b"Ordering").unwrap().as_str().unwrap(), ordering.as_bytes());
+
+            let content = doc.get_and_decode_page_content(page_id).unwrap();
+            let shown = content.operations.iter().find_map(|operation| match operation.operands.as_slice() {
+                [Object::String(text, _)] if operation.operator == "Tj" && text == &encode(caption) => Some(text),
+                _ => None,
+            });
+            assert!(shown.is_some(), "{font:?}: the caption is not shown as UCS-2");
+        }
+    }
+}
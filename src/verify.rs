@@ -0,0 +1,243 @@
+//! Checks that a file is the PDF document it is supposed to be: that it has the expected size,
+//! can be parsed, and that its cross reference sections and stream lengths agree with the bytes of
+//! the file.
+//!
+//! lopdf skips objects it cannot read when loading a document and tolerates some wrong stream
+//! lengths, so the offsets listed by the cross reference sections and the `/Length` of streams are
+//! checked against the raw bytes here. Only the latest definition of every object is
+//! checked, like a reader would use it.
+
+use std::fmt::{Display, Formatter};
+use std::path::Path;
+
+use lopdf::xref::XrefEntry;
+use lopdf::{Document, ObjectId};
+
+use crate::Error;
+
+/// The size a file is expected to have.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SizeTarget {
+    /// Exactly this amount of bytes.
+    Exact(usize),
+    /// Any size from `min` up to and including `max` bytes.
+    Range { min: usize, max: usize },
+    /// The size is not checked.
+    Any,
+}
+
+impl SizeTarget {
+    pub fn contains(&self, size: usize) -> bool {
+        match *self {
+            SizeTarget::Exact(expected) => size == expected,
+            SizeTarget::Range { min, max } => (min..=max).contains(&size),
+            SizeTarget::Any => true,
+        }
+    }
+}
+
+impl Display for SizeTarget {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SizeTarget::Exact(expected) => write!(f, "{expected} bytes"),
+            SizeTarget::Range { min, max } => write!(f, "{min} to {max} bytes"),
+            SizeTarget::Any => f.write_str("any size"),
+        }
+    }
+}
+
+/// Something found to be wrong with a file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Problem {
+    /// The file does not have the expected size.
+    Size { actual: usize, expected: SizeTarget },
+    /// lopdf could not parse the document, none of the other checks were done.
+    Parse(String),
+    /// The offset after `startxref` does not point to a cross reference section.
+    Startxref { offset: usize },
+    /// The cross reference section lists an offset at which the object is not defined.
+    XrefOffset { id: ObjectId, offset: usize },
+    /// The `/Length` of a stream does not match the amount of data up to `endstream`.
+    StreamLength { id: ObjectId, declared: usize, actual: usize },
+    /// The object is defined at the listed offset, but could not be parsed for another reason.
+    UnreadableObject { id: ObjectId },
+}
+
+impl Display for Problem {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Problem::Size { actual, expected } => write!(f, "the file is {actual} bytes large instead of {expected}"),
+            Problem::Parse(message) => write!(f, "the document cannot be parsed: {message}"),
+            Problem::Startxref { offset } => {
+                write!(f, "startxref points to offset {offset}, which is not a cross reference section")
+            }
+            Problem::XrefOffset { id: (number, generation), offset } => {
+                write!(f, "object {number} {generation} is not defined at offset {offset} listed for it")
+            }
+            Problem::StreamLength { id: (number, generation), declared, actual } => {
+                write!(f, "the stream of object {number} {generation} declares {declared} bytes but holds {actual}")
+            }
+            Problem::UnreadableObject { id: (number, generation) } => {
+                write!(f, "object {number} {generation} cannot be parsed")
+            }
+        }
+    }
+}
+
+/// The outcome of [`verify_pdf`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct VerifyReport {
+    /// Size of the file in bytes.
+    pub file_size: usize,
+    /// Amount of objects whose offset and stream were checked.
+    pub objects: usize,
+    /// Everything found to be wrong, in the order the checks were done.
+    pub problems: Vec<Problem>,
+}
+
+impl VerifyReport {
+    /// Whether no problems were found.
+    pub fn is_valid(&self) -> bool {
+        self.problems.is_empty()
+    }
+}
+
+impl Display for VerifyReport {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} bytes, {} objects checked", self.file_size, self.objects)?;
+        for problem in &self.problems {
+            write!(f, "\n{problem}")?;
+        }
+        Ok(())
+    }
+}
+
+/// Verifies the file at `path`, see the [module documentation](self). The file is read into memory
+/// at once.
+pub fn verify_pdf(path: impl AsRef<Path>, expected: SizeTarget) -> Result<VerifyReport, Error> {
+    Ok(verify_pdf_bytes(&std::fs::read(path)?, expected))
+}
+
+/// Verifies a file which has already been read into `bytes`.
+pub fn verify_pdf_bytes(bytes: &[u8], expected: SizeTarget) -> VerifyReport {
+    let mut report = VerifyReport { file_size: bytes.len(), objects: 0, problems: Vec::new() };
+    if !expected.contains(bytes.len()) {
+        report.problems.push(Problem::Size { actual: bytes.len(), expected });
+    }
+
+    if let Some(offset) = startxref(bytes) {
+        let section = &bytes[offset.min(bytes.len())..];
+        if !section.starts_with(b"xref") && object_header(section).is_none() {
+            report.problems.push(Problem::Startxref { offset });
+        }
+    }
+    let doc = match Document::load_mem(bytes) {
+        Ok(doc) => doc,
+        Err(error) => {
+            report.problems.push(Problem::Parse(error.to_string()));
+            return report;
+        }
+    };
+
+    for (&number, entry) in &doc.reference_table.entries {
+        let &XrefEntry::Normal { offset, generation } = entry else {
+            continue;
+        };
+        let (id, offset) = ((number, generation), offset as usize);
+        report.objects += 1;
+        if bytes.get(offset..).and_then(object_header) != Some(id) {
+            report.problems.push(Problem::XrefOffset { id, offset });
+            continue;
+        }
+        // lopdf reads some streams whose length is wrong nonetheless
+        match stream_lengths(&bytes[offset..], &doc) {
+            Some((declared, actual)) if declared != actual => {
+                report.problems.push(Problem::StreamLength { id, declared, actual })
+            }
+            _ if !doc.objects.contains_key(&id) => report.problems.push(Problem::UnreadableObject { id }),
+            _ => {}
+        }
+    }
+    report
+}
+
+/// Parses the offset following the last `startxref` keyword.
+pub(crate) fn startxref(bytes: &[u8]) -> Option<usize> {
+    const KEYWORD: &[u8] = b"startxref";
+    let start = bytes.windows(KEYWORD.len()).rposition(|window| window == KEYWORD)? + KEYWORD.len();
+    let (offset, _) = unsigned(skip_whitespace(&bytes[start..]))?;
+    Some(offset)
+}
+
+/// Parses the `<number> <generation> obj` at the start of `bytes`.
+fn object_header(bytes: &[u8]) -> Option<ObjectId> {
+    let (number, rest) = unsigned(bytes)?;
+    let (generation, rest) = unsigned(whitespace(rest)?)?;
+    whitespace(rest)?.starts_with(b"obj").then_some((number.try_into().ok()?, generation.try_into().ok()?))
+}
+
+/// Returns the declared and the actual length of the stream of the object at the start of
+/// `object`, if it is a stream. The actual length is the amount of bytes up to the next `endstream`
+/// keyword without the end of line marker preceding it, unless the declared length ends in front
+/// of that marker.
+fn stream_lengths(object: &[u8], doc: &Document) -> Option<(usize, usize)> {
+    // the dictionary ends with either keyword, the data of a stream may contain anything
+    let keyword = object.windows(6).position(|window| window == b"stream" || window == b"endobj")?;
+    let rest = object[keyword..].strip_prefix(b"stream")?;
+    let data = rest.strip_prefix(b"\r\n").or(rest.strip_prefix(b"\n"))?;
+    let declared = length(&object[..keyword], doc)?;
+
+    let ends_stream = |rest: &[u8]| {
+        let rest = rest.strip_prefix(b"\r\n").or(rest.strip_prefix(b"\n")).or(rest.strip_prefix(b"\r")).unwrap_or(rest);
+        rest.starts_with(b"endstream")
+    };
+    if data.get(declared..).is_some_and(ends_stream) {
+        return Some((declared, declared));
+    }
+    let end = find(data, b"endstream")?;
+    let actual = match &data[..end] {
+        [data @ .., b'\r', b'\n'] | [data @ .., b'\n' | b'\r'] => data.len(),
+        data => data.len(),
+    };
+    Some((declared, actual))
+}
+
+/// Reads the `/Length` from the stream dictionary `dictionary`, looking it up in `doc` if it is
+/// an indirect object.
+fn length(dictionary: &[u8], doc: &Document) -> Option<usize> {
+    const KEY: &[u8] = b"/Length";
+    let start = find(dictionary, KEY)? + KEY.len();
+    let (value, rest) = unsigned(whitespace(&dictionary[start..])?)?;
+    let reference = whitespace(rest).and_then(unsigned).and_then(|(generation, rest)| {
+        whitespace(rest)?.starts_with(b"R").then_some((value.try_into().ok()?, generation.try_into().ok()?))
+    });
+    match reference {
+        Some(id) => doc.get_object(id).ok()?.as_i64().ok()?.try_into().ok(),
+        None => Some(value),
+    }
+}
+
+fn find(bytes: &[u8], needle: &[u8]) -> Option<usize> {
+    bytes.windows(needle.len()).position(|window| window == needle)
+}
+
+/// Parses the decimal number at the start of `bytes` and returns it along with the rest.
+fn unsigned(bytes: &[u8]) -> Option<(usize, &[u8])> {
+    let digits = bytes.iter().take_while(|byte| byte.is_ascii_digit()).count();
+    let number = std::str::from_utf8(&bytes[..digits]).ok()?.parse().ok()?;
+    Some((number, &bytes[digits..]))
+}
+
+/// Skips the whitespace at the start of `bytes`, of which there must be some.
+fn whitespace(bytes: &[u8]) -> Option<&[u8]> {
+    let rest = skip_whitespace(bytes);
+    (rest.len() < bytes.len()).then_some(rest)
+}
+
+fn skip_whitespace(bytes: &[u8]) -> &[u8] {
+    let whitespace = bytes
+        .iter()
+        .take_while(|byte| matches!(byte, b'\0' | b'\t' | b'\n' | b'\x0C' | b'\r' | b' '))
+        .count();
+    &bytes[whitespace..]
+}
@@ -0,0 +1,14 @@
+//! Bindings for JavaScript, available with the `wasm` feature. Build the module with
+//! `cargo rustc --lib --release --target wasm32-unknown-unknown --features wasm --crate-type cdylib`
+//! and generate the bindings for it with `wasm-bindgen`.
+
+use wasm_bindgen::prelude::*;
+
+use crate::Options;
+
+/// Generates a document which is exactly `file_size_bytes` bytes large and returns its bytes as a
+/// `Uint8Array`.
+#[wasm_bindgen(js_name = generatePdfBytes)]
+pub fn generate_pdf_bytes(file_size_bytes: usize) -> Result<Vec<u8>, JsError> {
+    crate::generate_pdf_bytes(file_size_bytes, &Options::default()).map_err(|error| JsError::new(&error.to_string()))
+}
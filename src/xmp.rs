@@ -0,0 +1,207 @@
+//! XMP metadata packets mirroring the document information dictionary.
+
+use crate::options::Info;
+
+/// Renders an XMP packet from the template below, filling in every entry of `info` which is set.
+pub(crate) fn packet(info: &Info) -> Vec<u8> {
+    let mut fields = String::new();
+    if let Some(title) = &info.title {
+        fields += &format!("   <dc:title><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:title>\n", escape(title));
+    }
+    if let Some(author) = &info.author {
+        fields += &format!("   <dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>\n", escape(author));
+    }
+    if let Some(subject) = &info.subject {
+        fields += &format!("   <dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:description>\n", escape(subject));
+    }
+    if let Some(producer) = &info.producer {
+        fields += &format!("   <pdf:Producer>{}</pdf:Producer>\n", escape(producer));
+    }
+    if let Some(date) = info.creation_date.as_deref().and_then(iso_8601_date) {
+        fields += &format!("   <xmp:CreateDate>{date}</xmp:CreateDate>\n");
+    }
+
+    format!(
+        "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?>
+<x:xmpmeta xmlns:x=\"adobe:ns:meta/\">
+ <rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">
+  <rdf:Description rdf:about=\"\"
+    xmlns:dc=\"http://purl.org/dc/elements/1.1/\"
+    xmlns:pdf=\"http://ns.adobe.com/pdf/1.3/\"
+    xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\">
+   <dc:format>application/pdf</dc:format>
+{fields}  </rdf:Description>
+ </rdf:RDF>
+</x:xmpmeta>
+<?xpacket end=\"w\"?>"
+    )
+    .into_bytes()
+}
+
+fn escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for char in text.chars() {
+        match char {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            _ => escaped.push(char),
+        }
+    }
+    escaped
+}
+
+/// Converts a PDF date string like `D:20240131120000+01'00'` into the ISO 8601 form XMP expects.
+/// Returns `None` if the date does not follow the PDF date format.
+fn iso_8601_date(pdf_date: &str) -> Option<String> {
+    let date = pdf_date.strip_prefix("D:").unwrap_or(pdf_date);
+    let digits = date.bytes().take_while(u8::is_ascii_digit).count();
+    if digits < 4 || digits % 2 != 0 || digits > 14 {
+        return None;
+    }
+    let (timestamp, zone) = date.split_at(digits);
+
+    // year, then month, day, hour, minute and second as far as they are given
+    let mut iso = timestamp[..4].to_string();
+    for (index, separator) in ["-", "-", "T", ":", ":"].iter().enumerate() {
+        let start = 4 + index * 2;
+        if start >= digits {
+            break;
+        }
+        iso += separator;
+        iso += &timestamp[start..start + 2];
+    }
+    // XMP requires minutes whenever the hour is given
+    if digits == 10 {
+        iso += ":00";
+    }
+
+    match zone.as_bytes() {
+        [] => {}
+        [b'Z', ..] => iso.push('Z'),
+        [sign @ (b'+' | b'-'), h1, h2, rest @ ..] if h1.is_ascii_digit() && h2.is_ascii_digit() => {
+            let minutes = match rest {
+                [b'\'', m1, m2, ..] if m1.is_ascii_digit() && m2.is_ascii_digit() => [*m1, *m2],
+                _ => [b'0', b'0'],
+            };
+            iso += &format!(
+                "{}{}{}:{}{}",
+                *sign as char, *h1 as char, *h2 as char, minutes[0] as char, minutes[1] as char
+            );
+        }
+        _ => return None,
+    }
+    Some(iso)
+}
+
+#[cfg(test)]
+mod tests {
+    use lopdf::Document;
+
+    use crate::verify::{verify_pdf_bytes, SizeTarget};
+    use crate::{generate_pdf_bytes, minimum_size, Options, PaddingStrategy};
+
+    /// Checks that `xml` is well-formed: every element is closed in order, text and attribute values
+    /// only use the predefined entities and there is a single root element. Returns the text of every
+    /// element along with its name, unescaped.
+    fn parse(xml: &str) -> Vec<(String, String)> {
+        let unescape = |text: &str| {
+            let mut rest = text;
+            let mut unescaped = String::new();
+            while let Some(start) = rest.find('&') {
+                unescaped += &rest[..start];
+                let end = rest[start..].find(';').map(|end| start + end).expect("an unterminated entity");
+                unescaped += match &rest[start..=end] {
+                    "&amp;" => "&",
+                    "&lt;" => "<",
+                    "&gt;" => ">",
+                    "&quot;" => "\"",
+                    entity => panic!("unknown entity {entity}"),
+                };
+                rest = &rest[end + 1..];
+            }
+            unescaped + rest
+        };
+
+        let mut open: Vec<&str> = Vec::new();
+        let mut roots = 0;
+        let mut texts = Vec::new();
+        let mut rest = xml;
+        while let Some(start) = rest.find('<') {
+            assert!(!rest[..start].contains('>'), "a bare > in {}", &rest[..start]);
+            let text = unescape(&rest[..start]);
+            if !text.trim().is_empty() {
+                assert!(open.last().is_some(), "text {text} outside of all elements");
+                texts.push((open.last().unwrap().to_string(), text));
+            }
+            let end = start + rest[start..].find('>').expect("an unterminated tag");
+            let tag = &rest[start + 1..end];
+            rest = &rest[end + 1..];
+            if let Some(instruction) = tag.strip_prefix('?') {
+                assert!(instruction.ends_with('?'), "an unterminated processing instruction");
+            } else if let Some(name) = tag.strip_prefix('/') {
+                assert_eq!(open.pop(), Some(name), "a misplaced end tag");
+            } else {
+                let (name, attributes) = tag.split_once(char::is_whitespace).unwrap_or((tag, ""));
+                // every attribute value is quoted and holds no markup
+                for (index, value) in attributes.trim_end_matches('/').split('"').enumerate() {
+                    if index % 2 == 1 {
+                        assert!(!value.contains('<'), "markup in the attribute value {value}");
+                        unescape(value);
+                    } else {
+                        let names = value.trim();
+                        assert!(names.is_empty() || names.ends_with('='), "the attribute {names} is not quoted");
+                    }
+                }
+                if open.is_empty() {
+                    roots += 1;
+                }
+                if !tag.ends_with('/') {
+                    open.push(name);
+                }
+            }
+        }
+        assert!(rest.trim().is_empty() && open.is_empty(), "unclosed elements {open:?}");
+        assert_eq!(roots, 1);
+        texts
+    }
+
+    #[test]
+    fn every_size_with_a_well_formed_packet() {
+        let title = "Sizes <&> \"quoted\" – größer";
+        let options = Options::default()
+            .title(title)
+            .author("A & B")
+            .subject("<subject>")
+            .producer("generatePDF")
+            .creation_date("D:20240131120000+01'00'")
+            .xmp_metadata(true);
+        for padding in [PaddingStrategy::Text, PaddingStrategy::FormFields] {
+            let options = options.clone().padding(padding);
+            let minimum = minimum_size(&options).unwrap();
+            for size in (minimum..minimum + 16).chain(10_000 - 16..10_000 + 16) {
+                let bytes = generate_pdf_bytes(size, &options).unwrap();
+                assert!(verify_pdf_bytes(&bytes, SizeTarget::Exact(size)).is_valid(), "{padding:?} {size}");
+
+                let doc = Document::load_mem(&bytes).unwrap();
+                let metadata = doc.catalog().unwrap().get(b"Metadata").unwrap().as_reference().unwrap();
+                let metadata = doc.get_object(metadata).unwrap().as_stream().unwrap();
+                assert_eq!(metadata.dict.get(b"Subtype").unwrap().as_name().unwrap(), b"XML");
+                let packet = std::str::from_utf8(&metadata.content).unwrap();
+                assert!(packet.starts_with("<?xpacket begin=\"\u{feff}\""), "{packet}");
+                assert!(packet.ends_with("<?xpacket end=\"w\"?>"), "{packet}");
+
+                let texts = parse(packet);
+                let text = |element: &str| {
+                    texts.iter().find(|(name, _)| name == element).map(|(_, text)| text.as_str())
+                };
+                assert_eq!(text("dc:format"), Some("application/pdf"));
+                assert_eq!(text("rdf:li"), Some(title));
+                assert_eq!(texts.iter().filter(|(name, _)| name == "rdf:li").count(), 3);
+                assert_eq!(text("pdf:Producer"), Some("generatePDF"));
+                assert_eq!(text("xmp:CreateDate"), Some("2024-01-31T12:00:00+01:00"));
+            }
+        }
+    }
+}
@@ -0,0 +1,65 @@
+//! Counts the bytes allocated while generating documents, which a [`Generator`] should mostly take
+//! from its pool once it has generated a few documents.
+
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use generatePDF::{Generator, Options, PaddingStrategy};
+
+struct CountingAllocator;
+
+static ALLOCATED: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for CountingAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc(layout)
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        ALLOCATED.fetch_add(layout.size(), Ordering::Relaxed);
+        System.alloc_zeroed(layout)
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        ALLOCATED.fetch_add(new_size, Ordering::Relaxed);
+        System.realloc(ptr, layout, new_size)
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout)
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator;
+
+/// Size of the documents, large enough for their padding to stand out from everything else.
+const SIZE: usize = 4_000_000;
+
+/// Returns the amount of bytes allocated while `generate` runs.
+fn allocated(generate: impl FnOnce()) -> usize {
+    let before = ALLOCATED.load(Ordering::Relaxed);
+    generate();
+    ALLOCATED.load(Ordering::Relaxed) - before
+}
+
+#[test]
+fn generators_reuse_their_buffers() {
+    for padding in [PaddingStrategy::Text, PaddingStrategy::HexText, PaddingStrategy::RawImage] {
+        let mut generator = Generator::new(Options::default().padding(padding));
+        let mut output = Vec::new();
+        let mut buf = vec![0; SIZE];
+        for size in [SIZE, SIZE - 1000, SIZE] {
+            generator.generate_into(size, &mut output).unwrap();
+            generator.generate_into_slice(SIZE, &mut buf).unwrap();
+        }
+        // the structure of every candidate document is still allocated, which the padding dwarfs
+        for size in [SIZE - 10, SIZE] {
+            let into = allocated(|| generator.generate_into(size, &mut output).unwrap());
+            assert!(into < SIZE / 4, "{padding:?}: generate_into allocated {into} bytes");
+            let into_slice = allocated(|| generator.generate_into_slice(SIZE, &mut buf).unwrap());
+            assert!(into_slice < SIZE / 4, "{padding:?}: generate_into_slice allocated {into_slice} bytes");
+        }
+    }
+}